@@ -0,0 +1,446 @@
+//! Connection acquisition helpers.
+
+use std::{
+    io,
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, Instant},
+};
+
+use futures_util::future::BoxFuture;
+use sqlx::{pool::PoolConnection, Error, MySql, MySqlConnection, MySqlPool};
+use tokio_util::sync::CancellationToken;
+
+use crate::{config::PoolOptions, error::AcquireCancelled};
+
+/// Maximum length of a tenant tag accepted by [`acquire_tagged`].
+pub const MAX_TENANT_TAG_LEN: usize = 64;
+
+/// Sampled logging of slow connection acquires.
+///
+/// An acquire is slow when it takes at least
+/// [`acquire_slow_threshold_ms`](PoolOptions::acquire_slow_threshold_ms). Only one in every
+/// [`acquire_slow_log_sample`](PoolOptions::acquire_slow_log_sample) slow acquires emits a
+/// warning, which keeps logs readable at high QPS. Slow acquires are counted with an atomic
+/// counter, so a single instance can be shared across tasks.
+#[derive(Debug)]
+pub struct SlowAcquireLog {
+    threshold: Duration,
+    sample: u64,
+    slow_count: AtomicU64,
+}
+
+impl SlowAcquireLog {
+    /// Creates a sampler logging one in `sample` acquires slower than `threshold`.
+    ///
+    /// A `sample` of 0 or 1 logs every slow acquire.
+    pub fn new(threshold: Duration, sample: u32) -> Self {
+        SlowAcquireLog {
+            threshold,
+            sample: u64::from(sample.max(1)),
+            slow_count: AtomicU64::new(0),
+        }
+    }
+
+    /// Creates a sampler from the slow-acquire settings of `options`.
+    ///
+    /// Returns `None` when no slow-acquire threshold is configured.
+    pub fn from_options(options: &PoolOptions) -> Option<Self> {
+        options.acquire_slow_threshold_ms.map(|threshold| {
+            SlowAcquireLog::new(
+                Duration::from_millis(threshold),
+                options.acquire_slow_log_sample.unwrap_or(1),
+            )
+        })
+    }
+
+    /// Acquires a connection from `pool`, recording how long it took.
+    pub async fn acquire(&self, pool: &MySqlPool) -> Result<PoolConnection<MySql>, Error> {
+        let started = Instant::now();
+        let conn = pool.acquire().await?;
+        self.record(started.elapsed());
+        Ok(conn)
+    }
+
+    /// Records an acquire that took `elapsed`, returning `true` if a warning was emitted.
+    pub fn record(&self, elapsed: Duration) -> bool {
+        if elapsed < self.threshold {
+            return false;
+        }
+
+        let slow_count = self.slow_count.fetch_add(1, Ordering::Relaxed);
+        if !slow_count.is_multiple_of(self.sample) {
+            return false;
+        }
+
+        warn!(
+            elapsed = ?elapsed,
+            threshold = ?self.threshold,
+            sampled = self.sample,
+            "Slow connection acquire from the TiDB pool"
+        );
+        true
+    }
+}
+
+/// Acquires a connection and tags it with `tenant` for auditing.
+///
+/// Runs `SET @tenant = ?` on the connection after acquiring it, so the tenant can be read back
+/// with `SELECT @tenant` (or inspected in audit tooling) while the connection is in use. The
+/// tag must be non-empty and at most [`MAX_TENANT_TAG_LEN`] characters, otherwise
+/// [`Error::InvalidArgument`] is returned without acquiring a connection.
+///
+/// The tag is not cleared when the connection goes back to the pool: the variable lives in the
+/// session, and clearing it on every release would cost a round trip on every pooled
+/// connection, tagged or not. A connection later acquired without this helper may therefore
+/// still carry a previous tenant's tag, so never rely on `@tenant` being `NULL`. Clear it
+/// before dropping the connection if that matters:
+///
+/// ```rust,ignore
+/// let mut conn = acquire_tagged(&pool, "acme").await?;
+/// // ...
+/// sqlx::query("SET @tenant = NULL").execute(&mut *conn).await?;
+/// ```
+pub async fn acquire_tagged(
+    pool: &MySqlPool,
+    tenant: &str,
+) -> Result<PoolConnection<MySql>, Error> {
+    if tenant.is_empty() || tenant.chars().count() > MAX_TENANT_TAG_LEN {
+        return Err(Error::InvalidArgument(format!(
+            "tenant tag must be between 1 and {} characters",
+            MAX_TENANT_TAG_LEN
+        )));
+    }
+
+    let mut conn = pool.acquire().await?;
+    sqlx::query("SET @tenant = ?")
+        .bind(tenant)
+        .execute(&mut *conn)
+        .await?;
+    Ok(conn)
+}
+
+/// Acquires a connection from the pool, giving up as soon as `token` is cancelled.
+///
+/// Races [`MySqlPool::acquire`] against the token so that request-scoped work stops waiting for
+/// a connection once the request is abandoned. If the token fires first, or was already
+/// cancelled, the pending acquire is dropped and an [`Error::Io`] wrapping [`AcquireCancelled`]
+/// is returned; use [`is_acquire_cancelled`] to tell it apart from other failures, including
+/// interrupted I/O. The pool's own `acquire_timeout` still applies.
+///
+/// ## Example:
+/// ```rust,ignore
+/// let mut conn = acquire_cancellable(&pool, &request_token).await?;
+/// ```
+pub async fn acquire_cancellable(
+    pool: &MySqlPool,
+    token: &CancellationToken,
+) -> Result<PoolConnection<MySql>, Error> {
+    match token.run_until_cancelled(pool.acquire()).await {
+        Some(result) => result,
+        None => {
+            debug!("TiDB connection acquire cancelled");
+            Err(Error::Io(io::Error::other(AcquireCancelled)))
+        }
+    }
+}
+
+/// Acquires up to `n` connections at once for parallel work, waiting at most `timeout` overall.
+///
+/// `n` is capped at the pool's `max_connections`, so asking for more than the pool can ever
+/// hold returns fewer connections instead of waiting forever. Connections are acquired one after
+/// another; if any acquire fails, or `timeout` elapses first ([`Error::PoolTimedOut`]), the
+/// connections acquired so far are released back to the pool and the error is returned.
+///
+/// ## Example:
+/// ```rust,ignore
+/// let conns = acquire_many(&pool, 4, Duration::from_secs(5)).await?;
+/// let results = join_all(conns.into_iter().zip(shards).map(|(mut conn, shard)| async move {
+///     sqlx::query("UPDATE jobs SET done = 1 WHERE shard = ?").bind(shard).execute(&mut *conn).await
+/// }))
+/// .await;
+/// ```
+pub async fn acquire_many(
+    pool: &MySqlPool,
+    n: usize,
+    timeout: Duration,
+) -> Result<Vec<PoolConnection<MySql>>, Error> {
+    let max_connections = pool.options().get_max_connections() as usize;
+    let n = n.min(max_connections);
+
+    let mut conns = Vec::with_capacity(n);
+    let acquire_all = async {
+        while conns.len() < n {
+            conns.push(pool.acquire().await?);
+        }
+        Ok(())
+    };
+
+    let result = match tokio::time::timeout(timeout, acquire_all).await {
+        Ok(result) => result,
+        Err(_) => Err(Error::PoolTimedOut),
+    };
+    if let Err(err) = result {
+        debug!(
+            "Releasing {} of {} TiDB connections after a failed batch acquire: {}",
+            conns.len(),
+            n,
+            err
+        );
+        return Err(err);
+    }
+    Ok(conns)
+}
+
+/// Runs `f` on a pooled connection and returns the connection to the pool afterwards.
+///
+/// If `f` panics, the connection is still released while the panic unwinds, and a warning is
+/// logged so that panics in tasks holding connections can be diagnosed instead of showing up
+/// only as unexplained pool churn.
+///
+/// ## Example:
+/// ```rust,ignore
+/// let name: String = with_connection(&pool, |conn| {
+///     Box::pin(async move {
+///         sqlx::query_scalar("SELECT name FROM users WHERE id = ?").bind(42).fetch_one(conn).await
+///     })
+/// })
+/// .await?;
+/// ```
+pub async fn with_connection<F, T>(pool: &MySqlPool, f: F) -> Result<T, Error>
+where
+    F: for<'c> FnOnce(&'c mut MySqlConnection) -> BoxFuture<'c, Result<T, Error>>,
+{
+    let mut conn = pool.acquire().await?;
+    let _guard = PanicGuard;
+    f(&mut conn).await
+}
+
+/// Logs a warning when dropped while the current thread is panicking.
+struct PanicGuard;
+
+impl Drop for PanicGuard {
+    fn drop(&mut self) {
+        if std::thread::panicking() {
+            warn!("Task panicked while holding a TiDB connection; releasing it to the pool");
+        }
+    }
+}
+
+/// Returns `true` if `err` was returned by [`acquire_cancellable`] because its token fired.
+///
+/// Other errors, such as an [`io::ErrorKind::Interrupted`] I/O error from the driver, return
+/// `false`.
+pub fn is_acquire_cancelled(err: &Error) -> bool {
+    match err {
+        Error::Io(io_err) => io_err
+            .get_ref()
+            .is_some_and(|inner| inner.is::<AcquireCancelled>()),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        build_pool_from_config,
+        test_support::{capture_logs, integration_config},
+    };
+
+    /// Test that with a sample rate of 10, one in ten slow acquires is logged.
+    #[test]
+    fn test_slow_acquire_sampling() {
+        let log = SlowAcquireLog::new(Duration::from_millis(100), 10);
+
+        let logged = (0..100)
+            .filter(|_| log.record(Duration::from_millis(250)))
+            .count();
+
+        assert_eq!(logged, 10);
+    }
+
+    /// Test that fast acquires are never logged nor counted towards the sample.
+    #[test]
+    fn test_fast_acquires_not_logged() {
+        let log = SlowAcquireLog::new(Duration::from_millis(100), 1);
+
+        assert!(!log.record(Duration::from_millis(5)));
+        assert!(log.record(Duration::from_millis(100)));
+    }
+
+    /// Test that no sampler is built without a threshold.
+    #[test]
+    fn test_from_options_requires_threshold() {
+        let mut options = PoolOptions::default();
+        assert!(SlowAcquireLog::from_options(&options).is_none());
+
+        options.acquire_slow_threshold_ms = Some(50);
+        options.acquire_slow_log_sample = Some(4);
+        let log = SlowAcquireLog::from_options(&options).expect("Sampler should be built");
+
+        assert_eq!(log.threshold, Duration::from_millis(50));
+        assert_eq!(log.sample, 4);
+    }
+
+    /// Test that empty and oversized tenant tags are rejected before acquiring.
+    #[tokio::test]
+    async fn test_acquire_tagged_validates_tenant() {
+        let pool = MySqlPool::connect_lazy("mysql://root@127.0.0.1:1/test")
+            .expect("Failed to build lazy pool");
+
+        for tenant in [String::new(), "t".repeat(MAX_TENANT_TAG_LEN + 1)] {
+            let err = acquire_tagged(&pool, &tenant)
+                .await
+                .expect_err("Invalid tenant should be rejected");
+            assert!(matches!(err, Error::InvalidArgument(_)));
+        }
+    }
+
+    /// Test that the tenant can be read back from the session.
+    #[tokio::test]
+    #[ignore = "requires a running TiDB instance"]
+    async fn test_acquire_tagged_sets_session_variable() {
+        let pool = build_pool_from_config(integration_config())
+            .await
+            .expect("Failed to build pool");
+
+        let mut conn = acquire_tagged(&pool, "acme").await.expect("Failed to acquire");
+        let tenant: String = sqlx::query_scalar("SELECT @tenant")
+            .fetch_one(&mut *conn)
+            .await
+            .expect("Failed to read tenant");
+
+        assert_eq!(tenant, "acme");
+    }
+
+    /// Test that an already-cancelled token aborts the acquire without touching the server.
+    #[tokio::test]
+    async fn test_acquire_cancellable_with_cancelled_token() {
+        let pool = sqlx::MySqlPool::connect_lazy("mysql://root@127.0.0.1:1/test")
+            .expect("Failed to build lazy pool");
+        let token = CancellationToken::new();
+        token.cancel();
+
+        let err = acquire_cancellable(&pool, &token)
+            .await
+            .expect_err("Cancelled acquire should fail");
+
+        assert!(is_acquire_cancelled(&err));
+        assert!(err.to_string().ends_with("connection acquire cancelled"), "{}", err);
+    }
+
+    /// Test that interrupted I/O is not mistaken for a cancelled acquire.
+    #[test]
+    fn test_interrupted_io_is_not_cancellation() {
+        let err = Error::Io(io::Error::new(io::ErrorKind::Interrupted, "interrupted"));
+        assert!(!is_acquire_cancelled(&err));
+        assert!(!is_acquire_cancelled(&Error::PoolTimedOut));
+    }
+
+    /// Test that cancelling the token aborts an acquire waiting on a saturated pool.
+    #[tokio::test]
+    #[ignore = "requires a running TiDB instance"]
+    async fn test_acquire_cancellable_on_saturated_pool() {
+        let mut config = integration_config();
+        config.pool_options.max_connections = 1;
+        config.pool_options.min_connections = 0;
+        let acquire_timeout = Duration::from_secs(config.pool_options.acquire_timeout);
+        let pool = build_pool_from_config(config).await.expect("Failed to build pool");
+        let _held = pool.acquire().await.expect("Failed to acquire connection");
+
+        let token = CancellationToken::new();
+        let canceller = token.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            canceller.cancel();
+        });
+
+        let started = Instant::now();
+        let err = acquire_cancellable(&pool, &token)
+            .await
+            .expect_err("Pending acquire should be cancelled");
+
+        assert!(is_acquire_cancelled(&err));
+        assert!(started.elapsed() < acquire_timeout);
+    }
+
+    /// Test that `acquire_many` returns the requested connections, capped at `max_connections`.
+    #[tokio::test]
+    #[ignore = "requires a running TiDB instance"]
+    async fn test_acquire_many() {
+        let mut config = integration_config();
+        config.pool_options.max_connections = 5;
+        config.pool_options.min_connections = 0;
+        let pool = build_pool_from_config(config).await.expect("Failed to build pool");
+
+        let conns = acquire_many(&pool, 3, Duration::from_secs(5))
+            .await
+            .expect("Failed to acquire connections");
+        assert_eq!(conns.len(), 3);
+        assert_eq!(pool.size(), 3);
+        drop(conns);
+
+        let conns = acquire_many(&pool, 10, Duration::from_secs(5))
+            .await
+            .expect("Failed to acquire connections");
+        assert_eq!(conns.len(), 5);
+    }
+
+    /// Test that a batch that cannot complete in time fails and releases what it acquired.
+    #[tokio::test]
+    #[ignore = "requires a running TiDB instance"]
+    async fn test_acquire_many_times_out_and_releases() {
+        let mut config = integration_config();
+        config.pool_options.max_connections = 5;
+        config.pool_options.min_connections = 0;
+        let pool = build_pool_from_config(config).await.expect("Failed to build pool");
+        let _held = acquire_many(&pool, 3, Duration::from_secs(5))
+            .await
+            .expect("Failed to acquire connections");
+
+        let err = acquire_many(&pool, 3, Duration::from_millis(200))
+            .await
+            .expect_err("Batch should time out");
+
+        assert!(matches!(err, Error::PoolTimedOut));
+        assert_eq!(pool.num_idle(), 2);
+    }
+
+    /// Test that the guard warns only when dropped during a panic.
+    #[test]
+    fn test_panic_guard_warns_on_unwind() {
+        let (_guard, logs) = capture_logs();
+
+        drop(PanicGuard);
+        assert!(logs.contents().is_empty());
+
+        let result = std::panic::catch_unwind(|| {
+            let _guard = PanicGuard;
+            panic!("boom");
+        });
+
+        assert!(result.is_err());
+        assert!(logs.contents().contains("panicked while holding a TiDB connection"));
+    }
+
+    /// Test that a panicking closure passed to `with_connection` triggers the warning.
+    #[tokio::test]
+    #[ignore = "requires a running TiDB instance"]
+    async fn test_with_connection_logs_panics() {
+        let pool = build_pool_from_config(integration_config())
+            .await
+            .expect("Failed to build pool");
+        let (_guard, logs) = capture_logs();
+
+        let result = tokio::spawn(async move {
+            with_connection(&pool, |_conn| -> BoxFuture<'_, Result<(), Error>> {
+                Box::pin(async { panic!("boom") })
+            })
+            .await
+        })
+        .await;
+
+        assert!(result.expect_err("Task should panic").is_panic());
+        assert!(logs.contents().contains("panicked while holding a TiDB connection"));
+    }
+}
@@ -0,0 +1,153 @@
+//! Builder for constructing a [`TiDBConfig`] in code.
+
+use crate::config::{PoolOptions, TiDBConfig};
+
+/// Chainable builder for [`TiDBConfig`].
+///
+/// Fields that are not set keep their [`Default`] values, so code using the builder keeps
+/// compiling when new configuration fields are added. `host`, `username` and `database_name`
+/// are required; [`build`][Self::build] fails if any of them is missing, reporting all missing
+/// fields at once.
+///
+/// ## Example:
+/// ```rust
+/// let config = tidb_pool::TiDBConfig::builder()
+///     .host("127.0.0.1")
+///     .port(4000)
+///     .username("root")
+///     .password("secret")
+///     .database_name("test_db")
+///     .build()?;
+/// assert_eq!(config.get_host(), "127.0.0.1:4000");
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct TiDBConfigBuilder {
+    config: TiDBConfig,
+}
+
+impl TiDBConfig {
+    /// Returns a [`TiDBConfigBuilder`] with every field set to its default.
+    pub fn builder() -> TiDBConfigBuilder {
+        TiDBConfigBuilder::default()
+    }
+}
+
+impl TiDBConfigBuilder {
+    /// Sets [`TiDBConfig::host`].
+    pub fn host(mut self, host: impl Into<String>) -> Self {
+        self.config.host = host.into();
+        self
+    }
+
+    /// Sets [`TiDBConfig::port`].
+    pub fn port(mut self, port: u16) -> Self {
+        self.config.port = Some(port);
+        self
+    }
+
+    /// Sets [`TiDBConfig::username`].
+    pub fn username(mut self, username: impl Into<String>) -> Self {
+        self.config.username = username.into();
+        self
+    }
+
+    /// Sets [`TiDBConfig::password`].
+    pub fn password(mut self, password: impl Into<String>) -> Self {
+        self.config.password = password.into();
+        self
+    }
+
+    /// Sets [`TiDBConfig::database_name`].
+    pub fn database_name(mut self, database_name: impl Into<String>) -> Self {
+        self.config.database_name = database_name.into();
+        self
+    }
+
+    /// Sets [`TiDBConfig::ssl_ca`].
+    pub fn ssl_ca(mut self, ssl_ca: impl Into<String>) -> Self {
+        self.config.ssl_ca = Some(ssl_ca.into());
+        self
+    }
+
+    /// Sets [`TiDBConfig::pool_options`].
+    pub fn pool_options(mut self, pool_options: PoolOptions) -> Self {
+        self.config.pool_options = pool_options;
+        self
+    }
+
+    /// Validates the configuration and returns it.
+    ///
+    /// Fails with [`ConfigError::MissingField`](crate::ConfigError::MissingField) if `host`,
+    /// `username` or `database_name` was not set, and with any other error reported by
+    /// [`TiDBConfig::validate`].
+    pub fn build(self) -> anyhow::Result<TiDBConfig> {
+        self.config.validate()?;
+        Ok(self.config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error::ConfigError, test_support::temp_ca_file};
+
+    fn complete_builder() -> TiDBConfigBuilder {
+        TiDBConfig::builder()
+            .host("localhost")
+            .username("root")
+            .password("secret")
+            .database_name("test_db")
+    }
+
+    /// Test that a builder with every required field produces the expected configuration.
+    #[test]
+    fn test_build_success() {
+        let options = PoolOptions {
+            max_connections: 25,
+            ..PoolOptions::default()
+        };
+
+        let ssl_ca = temp_ca_file("builder");
+        let config = complete_builder()
+            .port(4001)
+            .ssl_ca(ssl_ca.path())
+            .pool_options(options)
+            .build()
+            .expect("Failed to build config");
+
+        assert_eq!(config.host, "localhost");
+        assert_eq!(config.port, Some(4001));
+        assert_eq!(config.username, "root");
+        assert_eq!(config.password, "secret");
+        assert_eq!(config.database_name, "test_db");
+        assert_eq!(config.ssl_ca.as_deref(), Some(ssl_ca.path()));
+        assert_eq!(config.pool_options.max_connections, 25);
+        assert_eq!(config.pool_options.min_connections, 1);
+    }
+
+    /// Test that each missing required field is reported by name.
+    #[test]
+    fn test_build_missing_fields() {
+        let cases = [
+            (complete_builder().host(""), "host"),
+            (complete_builder().username(""), "username"),
+            (complete_builder().database_name(""), "databaseName"),
+        ];
+
+        for (builder, field) in cases {
+            let err = builder.build().expect_err("Build should fail");
+
+            match err.downcast_ref::<ConfigError>() {
+                Some(ConfigError::MissingField(missing)) => assert_eq!(*missing, field),
+                other => panic!("unexpected error for `{}`: {:?}", field, other),
+            }
+        }
+
+        let err = TiDBConfig::builder().build().expect_err("Build should fail");
+        match err.downcast_ref::<ConfigError>() {
+            Some(ConfigError::Multiple(errors)) => assert_eq!(errors.len(), 3),
+            other => panic!("unexpected error: {:?}", other),
+        }
+    }
+}
@@ -1,11 +1,107 @@
-use std::ops::Deref;
+use std::{fmt, ops::Deref};
 
-#[derive(sqlx::FromRow, Debug)]
+/// A row count, as returned by `COUNT(*)`.
+///
+/// Decodes from a row with a single column, and as a column of its own, so it can be used in
+/// tuples such as `(ID, Count)`. Serializes as the bare integer, e.g. `5`.
+#[derive(sqlx::FromRow, sqlx::Type, Serialize, Deserialize)]
 #[sqlx(transparent)]
+#[serde(transparent)]
 pub struct Count(pub i64);
 
+/// Prints `Count(5)`, flagging negative values, which no `COUNT` query can produce, as
+/// `Count(-5, negative)`.
+impl fmt::Debug for Count {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0 < 0 {
+            write!(f, "Count({}, negative)", self.0)
+        } else {
+            write!(f, "Count({})", self.0)
+        }
+    }
+}
+
+/// Prints the bare number, e.g. `5`; width, fill and sign flags are honored.
+impl fmt::Display for Count {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { fmt::Display::fmt(&self.0, f) }
+}
+
 /// Enable `Deref` coercion `Count`.
 impl Deref for Count {
     type Target = i64;
     fn deref(&self) -> &Self::Target { &self.0 }
 }
+
+impl From<i64> for Count {
+    fn from(value: i64) -> Self { Count(value) }
+}
+
+impl From<Count> for i64 {
+    fn from(value: Count) -> Self { value.0 }
+}
+
+#[cfg(feature = "decimal")]
+impl Count {
+    /// Converts the count into an exact `rust_decimal::Decimal`.
+    pub fn to_decimal(&self) -> rust_decimal::Decimal { rust_decimal::Decimal::from(self.0) }
+}
+
+#[cfg(feature = "decimal")]
+impl From<Count> for rust_decimal::Decimal {
+    fn from(value: Count) -> Self { value.to_decimal() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test that negative counts are flagged in `Debug` output.
+    #[test]
+    fn test_count_debug_flags_negative() {
+        assert_eq!(format!("{:?}", Count(5)), "Count(5)");
+        assert_eq!(format!("{:?}", Count(-5)), "Count(-5, negative)");
+    }
+
+    /// Test converting between `Count` and `i64` in both directions.
+    #[test]
+    fn test_count_from_into_i64() {
+        let count: Count = 5.into();
+        assert_eq!(*count, 5);
+        assert_eq!(*Count::from(i64::MIN), i64::MIN);
+
+        let n: i64 = count.into();
+        assert_eq!(n, 5);
+        assert_eq!(i64::from(Count(-3)), -3);
+    }
+
+    /// Test that a count round-trips through JSON as the bare integer.
+    #[test]
+    fn test_count_serde_transparent() {
+        assert_eq!(serde_json::to_string(&Count(5)).unwrap(), "5");
+        assert_eq!(
+            serde_json::to_value([Count(1), Count(-2)]).unwrap(),
+            serde_json::json!([1, -2])
+        );
+        assert_eq!(*serde_json::from_str::<Count>("5").unwrap(), 5);
+        assert!(serde_json::from_str::<Count>("\"5\"").is_err());
+    }
+
+    /// Test that `Display` prints the bare number.
+    #[test]
+    fn test_count_display() {
+        assert_eq!(format!("{}", Count(42)), "42");
+        assert_eq!(format!("{}", Count(-3)), "-3");
+        assert_eq!(format!("{:>5}", Count(42)), "   42");
+        assert_eq!(Count(7).to_string(), "7");
+    }
+
+    /// Test converting a `Count` into a `Decimal`.
+    #[cfg(feature = "decimal")]
+    #[test]
+    fn test_count_to_decimal() {
+        use rust_decimal::Decimal;
+
+        assert_eq!(Count(42).to_decimal(), Decimal::from(42));
+        assert_eq!(Decimal::from(Count(-7)), Decimal::new(-7, 0));
+    }
+}
@@ -0,0 +1,147 @@
+//! Pools whose password is fetched from a rotating credential source.
+
+use std::{future::Future, sync::Arc};
+
+use sqlx::{pool::PoolConnection, Error, MySql, MySqlPool};
+
+use crate::{build_pool_from_config, config::TiDBConfig};
+
+/// A source of short-lived database passwords, such as IAM authentication tokens.
+///
+/// Implementors can use `async fn`:
+///
+/// ```rust,ignore
+/// impl CredentialProvider for IamTokens {
+///     async fn password(&self) -> Result<String, sqlx::Error> {
+///         self.client.generate_token().await.map_err(|err| sqlx::Error::Configuration(err.into()))
+///     }
+/// }
+/// ```
+pub trait CredentialProvider: Send + Sync + 'static {
+    /// Returns the password to use for the next connection.
+    fn password(&self) -> impl Future<Output = Result<String, Error>> + Send;
+}
+
+/// A pool that refreshes its password from a [`CredentialProvider`] before opening connections.
+///
+/// Whenever an acquire through [`CredentialPool::acquire`] is likely to open a new connection
+/// (no idle connection is available and the pool is below `max_connections`), a fresh password
+/// is fetched and installed in the pool's connect options first. Connections that sqlx opens on
+/// its own, e.g. to maintain `min_connections`, use the most recently fetched password.
+///
+/// Existing connections keep the credentials they were opened with until they are reaped by
+/// `idle_timeout` or `max_lifetime`; the server does not re-authenticate open sessions. Keep
+/// `max_lifetime` below the credential validity period if the server enforces expiry.
+///
+/// Cloning is cheap: clones share the same pool and provider.
+#[derive(Debug)]
+pub struct CredentialPool<P> {
+    pool: MySqlPool,
+    provider: Arc<P>,
+}
+
+impl<P> Clone for CredentialPool<P> {
+    fn clone(&self) -> Self {
+        CredentialPool {
+            pool: self.pool.clone(),
+            provider: Arc::clone(&self.provider),
+        }
+    }
+}
+
+impl<P: CredentialProvider> CredentialPool<P> {
+    /// The underlying connection pool.
+    ///
+    /// Acquiring directly from it skips the password refresh.
+    pub fn pool(&self) -> &MySqlPool {
+        &self.pool
+    }
+
+    /// Fetches a fresh password and uses it for connections opened from now on.
+    pub async fn refresh_credentials(&self) -> Result<(), Error> {
+        let password = self.provider.password().await?;
+        let options = (*self.pool.connect_options()).clone().password(&password);
+        self.pool.set_connect_options(options);
+        debug!("Refreshed TiDB credentials for new connections");
+        Ok(())
+    }
+
+    /// Acquires a connection, refreshing the password first if a new connection will be opened.
+    pub async fn acquire(&self) -> Result<PoolConnection<MySql>, Error> {
+        let max_connections = self.pool.options().get_max_connections();
+        if self.pool.num_idle() == 0 && self.pool.size() < max_connections {
+            self.refresh_credentials().await?;
+        }
+        self.pool.acquire().await
+    }
+}
+
+/// Builds a pool whose password comes from `provider` instead of `config.password`.
+///
+/// The initial password is fetched before the pool is built; see [`CredentialPool`] for when it
+/// is refreshed.
+///
+/// ## Example:
+/// ```rust,ignore
+/// let pool = build_pool_with_credentials(config, IamTokens::new(client)).await?;
+/// let mut conn = pool.acquire().await?;
+/// ```
+pub async fn build_pool_with_credentials<P: CredentialProvider>(
+    mut config: TiDBConfig,
+    provider: P,
+) -> Result<CredentialPool<P>, Error> {
+    config.password = provider.password().await?;
+    let pool = build_pool_from_config(config).await?;
+    Ok(CredentialPool {
+        pool,
+        provider: Arc::new(provider),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+    use crate::test_support::{debug_field, integration_config};
+
+    /// Provider returning `secret-1`, `secret-2`, ... on successive calls.
+    #[derive(Default)]
+    struct IncrementingProvider {
+        calls: AtomicUsize,
+    }
+
+    impl CredentialProvider for IncrementingProvider {
+        async fn password(&self) -> Result<String, Error> {
+            let call = self.calls.fetch_add(1, Ordering::SeqCst) + 1;
+            Ok(format!("secret-{}", call))
+        }
+    }
+
+    fn password_of(pool: &CredentialPool<IncrementingProvider>) -> String {
+        debug_field(&*pool.pool().connect_options(), "password")
+    }
+
+    /// Test that a fresh password is fetched for the pool and before opening new connections.
+    #[tokio::test]
+    async fn test_credentials_refreshed_before_new_connections() {
+        let mut config = integration_config();
+        config.port = Some(1);
+        config.pool_options.is_lazy = true;
+        config.pool_options.acquire_timeout = 1;
+
+        let pool = build_pool_with_credentials(config, IncrementingProvider::default())
+            .await
+            .expect("Failed to build lazy pool");
+        assert_eq!(password_of(&pool), r#"Some("secret-1")"#);
+
+        // Nothing listens on port 1, but the password is refreshed before connecting.
+        assert!(pool.acquire().await.is_err());
+        assert_eq!(password_of(&pool), r#"Some("secret-2")"#);
+
+        pool.refresh_credentials()
+            .await
+            .expect("Failed to refresh credentials");
+        assert_eq!(password_of(&pool), r#"Some("secret-3")"#);
+    }
+}
@@ -0,0 +1,708 @@
+//! Error types returned while loading configuration and building pools.
+
+use std::{fmt, io, path::PathBuf, time::Duration};
+
+use crate::config::SUPPORTED_AUTH_PLUGINS;
+
+/// Errors produced while loading or validating a [`Config`](crate::Config) or
+/// [`TiDBConfig`](crate::TiDBConfig).
+#[derive(Debug)]
+pub enum ConfigError {
+    /// A configuration file could not be read.
+    Io {
+        /// Path of the file that failed to load.
+        path: PathBuf,
+        /// Underlying I/O error.
+        source: io::Error,
+    },
+
+    /// A configuration file is not valid TOML or does not match the expected shape.
+    Parse {
+        /// Path of the file that failed to parse, if the error is tied to a single file.
+        path: Option<PathBuf>,
+        /// Dotted path of the offending key (e.g. `tidb.pool_options.maxConnections`), when it
+        /// can be determined.
+        field: Option<String>,
+        /// Underlying TOML error.
+        source: Box<toml::de::Error>,
+    },
+
+    /// A required field is missing or empty.
+    MissingField(&'static str),
+
+    /// The requested setting cannot be honored by the underlying driver or network stack.
+    Unsupported(String),
+
+    /// Certificate verification was requested but no CA certificate was provided.
+    SslCaRequired,
+
+    /// A field has a value that is not allowed, alone or in combination with other fields.
+    Invalid {
+        /// Name of the offending field, as written in the configuration file.
+        field: &'static str,
+        /// Why the value was rejected.
+        reason: String,
+    },
+
+    /// Several problems were found by [`TiDBConfig::validate`](crate::TiDBConfig::validate).
+    Multiple(Vec<ConfigError>),
+}
+
+impl ConfigError {
+    /// Returns a suggestion for fixing the error, appended to its `Display` output.
+    pub fn hint(&self) -> Option<String> {
+        let hint = match self {
+            ConfigError::Io { .. } => "check that the file exists and is readable".into(),
+            ConfigError::Parse { .. } => "check the TOML syntax and the field names".into(),
+            ConfigError::MissingField(field) => {
+                format!("set `{}` in the configuration or its environment variable", field)
+            }
+            ConfigError::Unsupported(_) => "remove the setting or use a supported value".into(),
+            ConfigError::SslCaRequired => {
+                "set `sslCa` to the CA certificate path, or disable `requireSsl` and `verifyCa`"
+                    .into()
+            }
+            ConfigError::Invalid { field: "sslCa", .. } => {
+                "check the path; relative paths are resolved from the working directory".into()
+            }
+            ConfigError::Invalid { .. } | ConfigError::Multiple(_) => return None,
+        };
+        Some(hint)
+    }
+
+    fn fmt_message(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io { path, source } => {
+                write!(f, "failed to read config file {}: {}", path.display(), source)
+            }
+            ConfigError::Parse {
+                path,
+                field,
+                source,
+            } => {
+                write!(f, "failed to parse config")?;
+                if let Some(path) = path {
+                    write!(f, " file {}", path.display())?;
+                }
+                if let Some(field) = field {
+                    write!(f, " at `{}`", field)?;
+                }
+                write!(f, ": {}", source)
+            }
+            ConfigError::MissingField(field) => {
+                write!(f, "missing required config field `{}`", field)
+            }
+            ConfigError::Unsupported(reason) => write!(f, "unsupported configuration: {}", reason),
+            ConfigError::SslCaRequired => {
+                write!(f, "SSL certificate verification requires `sslCa` to be set")
+            }
+            ConfigError::Invalid { field, reason } => {
+                write!(f, "invalid config field `{}`: {}", field, reason)
+            }
+            ConfigError::Multiple(errors) => {
+                write!(f, "{} configuration errors: ", errors.len())?;
+                for (i, error) in errors.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "; ")?;
+                    }
+                    write!(f, "{}", error)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_message(f)?;
+        write_hint(f, self.hint().as_deref())
+    }
+}
+
+/// Appends ` (hint: ...)` to an error message if there is a hint.
+fn write_hint(f: &mut fmt::Formatter<'_>, hint: Option<&str>) -> fmt::Result {
+    match hint {
+        Some(hint) => write!(f, " (hint: {})", hint),
+        None => Ok(()),
+    }
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConfigError::Io { source, .. } => Some(source),
+            ConfigError::Parse { source, .. } => Some(source.as_ref()),
+            ConfigError::MissingField(_)
+            | ConfigError::Unsupported(_)
+            | ConfigError::SslCaRequired
+            | ConfigError::Invalid { .. }
+            | ConfigError::Multiple(_) => None,
+        }
+    }
+}
+
+/// Errors produced while building and verifying a pool with [`preflight`](crate::preflight).
+#[derive(Debug)]
+pub enum PoolBuildError {
+    /// The configuration is invalid.
+    Config(ConfigError),
+
+    /// The pool could not be built or a query against the server failed.
+    Connect(sqlx::Error),
+
+    /// The server did not answer a readiness probe within the timeout.
+    NotReady {
+        /// How long readiness was awaited.
+        timeout: Duration,
+        /// The last error returned by a readiness probe, if any.
+        last_error: Option<sqlx::Error>,
+    },
+
+    /// The server is older than the configured minimum version.
+    ServerVersion {
+        /// Minimum version required by the configuration.
+        required: String,
+        /// Version reported by the server.
+        found: String,
+    },
+
+    /// The server requested an authentication plugin that the driver cannot negotiate.
+    UnsupportedAuthPlugin(String),
+
+    /// Several pools failed to build, each paired with its name; see
+    /// [`build_pools_from_config`](crate::build_pools_from_config).
+    Multiple(Vec<(String, PoolBuildError)>),
+
+    /// The same name was given to several configurations passed to
+    /// [`build_pools_from_config`](crate::build_pools_from_config).
+    DuplicateName(String),
+}
+
+impl PoolBuildError {
+    /// Wraps an error from building or using the pool, recognizing configuration errors and
+    /// unsupported authentication plugins.
+    pub(crate) fn from_connect_error(err: sqlx::Error) -> Self {
+        if let Some(plugin) = unsupported_auth_plugin(&err) {
+            return PoolBuildError::UnsupportedAuthPlugin(plugin.to_owned());
+        }
+        match err {
+            sqlx::Error::Configuration(err) => match err.downcast::<ConfigError>() {
+                Ok(err) => PoolBuildError::Config(*err),
+                Err(err) => PoolBuildError::Connect(sqlx::Error::Configuration(err)),
+            },
+            err => PoolBuildError::Connect(err),
+        }
+    }
+}
+
+/// Returns the plugin named by `err` if the handshake failed on an unknown authentication plugin.
+///
+/// The pool builders returning [`sqlx::Error`] pass this handshake failure through unchanged.
+///
+/// ## Example:
+/// ```rust,ignore
+/// if let Err(err) = build_pool_from_config(config).await {
+///     if let Some(plugin) = unsupported_auth_plugin(&err) {
+///         error!("The TiDB account uses the unsupported `{}` plugin", plugin);
+///     }
+/// }
+/// ```
+pub fn unsupported_auth_plugin(err: &sqlx::Error) -> Option<&str> {
+    match err {
+        sqlx::Error::Protocol(message) => message
+            .strip_prefix("unknown authentication plugin: ")
+            .map(str::trim),
+        _ => None,
+    }
+}
+
+/// Returns a suggestion for fixing a connection error, based on the error returned by the
+/// driver or the server.
+fn connect_hint(err: &sqlx::Error) -> Option<&'static str> {
+    match err {
+        sqlx::Error::Database(err) => {
+            let message = err.message();
+            if message.starts_with("Access denied") && message.contains("to database") {
+                Some("grant the user access to `databaseName`")
+            } else if message.starts_with("Access denied") {
+                Some("check `username` and `password`")
+            } else if message.starts_with("Unknown database") {
+                Some("check `databaseName` or create the database")
+            } else if message.starts_with("Unknown or incorrect time zone") {
+                Some("check `timeZone`, or load the time zone tables on the server")
+            } else {
+                None
+            }
+        }
+        sqlx::Error::Io(err) => match err.kind() {
+            io::ErrorKind::ConnectionRefused => {
+                Some("check `host` and `port`, and that TiDB is running")
+            }
+            io::ErrorKind::NotFound => {
+                Some("check that the `sslCa`, `sslClientCert` and `sslClientKey` files exist")
+            }
+            _ => None,
+        },
+        sqlx::Error::Tls(_) => Some("check `sslMode` and the SSL certificate files"),
+        sqlx::Error::PoolTimedOut => {
+            Some("check that TiDB is reachable from this host, or raise `acquireTimeout`")
+        }
+        _ => None,
+    }
+}
+
+impl PoolBuildError {
+    /// Returns a suggestion for fixing the error, appended to its `Display` output.
+    pub fn hint(&self) -> Option<String> {
+        let hint = match self {
+            // The configuration error carries its own hint
+            PoolBuildError::Config(_) | PoolBuildError::Multiple(_) => return None,
+            PoolBuildError::Connect(err) => connect_hint(err)?,
+            PoolBuildError::NotReady { last_error, .. } => last_error
+                .as_ref()
+                .and_then(connect_hint)
+                .unwrap_or("check that TiDB is running, or raise the readiness timeout"),
+            PoolBuildError::ServerVersion { .. } => {
+                "upgrade the server or lower `minServerVersion`"
+            }
+            PoolBuildError::UnsupportedAuthPlugin(_) => {
+                "switch the account to a supported plugin with `ALTER USER ... IDENTIFIED WITH`"
+            }
+            PoolBuildError::DuplicateName(_) => "give every pool a distinct name",
+        };
+        Some(hint.into())
+    }
+
+    fn fmt_message(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PoolBuildError::Config(err) => write!(f, "invalid TiDB configuration: {}", err),
+            PoolBuildError::Connect(err) => write!(f, "failed to connect to TiDB: {}", err),
+            PoolBuildError::NotReady {
+                timeout,
+                last_error: Some(err),
+            } => write!(f, "TiDB was not ready after {:?}: {}", timeout, err),
+            PoolBuildError::NotReady {
+                timeout,
+                last_error: None,
+            } => write!(f, "TiDB was not ready after {:?}", timeout),
+            PoolBuildError::ServerVersion { required, found } => write!(
+                f,
+                "TiDB server version `{}` is older than the required `{}`",
+                found, required
+            ),
+            PoolBuildError::UnsupportedAuthPlugin(plugin) => write!(
+                f,
+                "TiDB requested authentication plugin `{}`, which is not supported \
+                 (supported: {})",
+                plugin,
+                SUPPORTED_AUTH_PLUGINS.join(", ")
+            ),
+            PoolBuildError::Multiple(errors) => {
+                write!(f, "{} TiDB pools failed to build: ", errors.len())?;
+                for (i, (name, error)) in errors.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "; ")?;
+                    }
+                    write!(f, "`{}`: {}", name, error)?;
+                }
+                Ok(())
+            }
+            PoolBuildError::DuplicateName(name) => {
+                write!(f, "TiDB pool name `{}` is used more than once", name)
+            }
+        }
+    }
+}
+
+impl fmt::Display for PoolBuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_message(f)?;
+        write_hint(f, self.hint().as_deref())
+    }
+}
+
+impl From<ConfigError> for PoolBuildError {
+    fn from(err: ConfigError) -> Self {
+        PoolBuildError::Config(err)
+    }
+}
+
+impl std::error::Error for PoolBuildError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PoolBuildError::Config(err) => Some(err),
+            PoolBuildError::Connect(err) => Some(err),
+            PoolBuildError::NotReady { last_error, .. } => {
+                last_error.as_ref().map(|err| err as &(dyn std::error::Error + 'static))
+            }
+            PoolBuildError::ServerVersion { .. }
+            | PoolBuildError::UnsupportedAuthPlugin(_)
+            | PoolBuildError::Multiple(_)
+            | PoolBuildError::DuplicateName(_) => None,
+        }
+    }
+}
+
+/// Marker for an acquire abandoned by [`acquire_cancellable`](crate::acquire_cancellable)
+/// because its token fired.
+///
+/// Returned inside an [`sqlx::Error::Io`] of kind [`io::ErrorKind::Other`], so that it cannot be
+/// mistaken for an interrupted socket operation; use
+/// [`is_acquire_cancelled`](crate::is_acquire_cancelled) to detect it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AcquireCancelled;
+
+impl fmt::Display for AcquireCancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("connection acquire cancelled")
+    }
+}
+
+impl std::error::Error for AcquireCancelled {}
+
+/// Warnings reported by the server for a statement run by a mutating helper while
+/// [`treat_warnings_as_errors`](crate::PoolOptions::treat_warnings_as_errors) is set.
+///
+/// Returned as a [`sqlx::Error::Database`]; use `downcast_ref` on the database error to inspect
+/// the warnings.
+#[derive(Debug)]
+pub struct StatementWarnings {
+    message: String,
+    warnings: Vec<String>,
+}
+
+impl StatementWarnings {
+    /// Wraps the warnings returned by `SHOW WARNINGS`, formatted as `<Level> <Code>: <Message>`.
+    pub(crate) fn new(warnings: Vec<String>) -> Self {
+        let message = format!(
+            "statement produced {} warning(s): {}",
+            warnings.len(),
+            warnings.join("; ")
+        );
+        StatementWarnings { message, warnings }
+    }
+
+    /// The warnings, formatted as `<Level> <Code>: <Message>`.
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+}
+
+impl fmt::Display for StatementWarnings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for StatementWarnings {}
+
+impl sqlx::error::DatabaseError for StatementWarnings {
+    fn message(&self) -> &str {
+        &self.message
+    }
+
+    fn as_error(&self) -> &(dyn std::error::Error + Send + Sync + 'static) {
+        self
+    }
+
+    fn as_error_mut(&mut self) -> &mut (dyn std::error::Error + Send + Sync + 'static) {
+        self
+    }
+
+    fn into_error(self: Box<Self>) -> Box<dyn std::error::Error + Send + Sync + 'static> {
+        self
+    }
+
+    fn kind(&self) -> sqlx::error::ErrorKind {
+        sqlx::error::ErrorKind::Other
+    }
+}
+
+/// A delete refused by [`delete_where`](crate::delete_where) because its `WHERE` clause was
+/// empty, so it would have removed every row of the table.
+///
+/// Returned as a [`sqlx::Error::Database`] before anything is sent to the server; use
+/// `downcast_ref` on the database error to detect it.
+#[derive(Debug)]
+pub struct MissingWhereClause {
+    message: String,
+    table: String,
+}
+
+impl MissingWhereClause {
+    /// Refuses a full-table delete of `table`.
+    pub(crate) fn new(table: &str) -> Self {
+        let message = format!(
+            "refusing to delete every row of {}: a WHERE clause is required",
+            table
+        );
+        MissingWhereClause {
+            message,
+            table: table.to_owned(),
+        }
+    }
+
+    /// The table the delete targeted.
+    pub fn table(&self) -> &str {
+        &self.table
+    }
+}
+
+impl fmt::Display for MissingWhereClause {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for MissingWhereClause {}
+
+impl sqlx::error::DatabaseError for MissingWhereClause {
+    fn message(&self) -> &str {
+        &self.message
+    }
+
+    fn as_error(&self) -> &(dyn std::error::Error + Send + Sync + 'static) {
+        self
+    }
+
+    fn as_error_mut(&mut self) -> &mut (dyn std::error::Error + Send + Sync + 'static) {
+        self
+    }
+
+    fn into_error(self: Box<Self>) -> Box<dyn std::error::Error + Send + Sync + 'static> {
+        self
+    }
+
+    fn kind(&self) -> sqlx::error::ErrorKind {
+        sqlx::error::ErrorKind::Other
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load_config() -> Result<(), ConfigError> {
+        Err(ConfigError::MissingField("host"))
+    }
+
+    fn startup() -> anyhow::Result<()> {
+        load_config()?;
+        Ok(())
+    }
+
+    fn build() -> Result<(), PoolBuildError> {
+        load_config()?;
+        Ok(())
+    }
+
+    /// Test that `?` converts a `ConfigError` into `anyhow::Error`, keeping the message.
+    #[test]
+    fn test_config_error_into_anyhow() {
+        let err = startup().expect_err("startup should fail");
+
+        assert_eq!(
+            err.to_string(),
+            "missing required config field `host` \
+             (hint: set `host` in the configuration or its environment variable)"
+        );
+        assert!(err.downcast_ref::<ConfigError>().is_some());
+    }
+
+    /// Test that `?` converts a `ConfigError` into `PoolBuildError::Config`, and that the
+    /// result also converts into `anyhow::Error` with its source chain intact.
+    #[test]
+    fn test_config_error_into_pool_build_error() {
+        let err = build().expect_err("build should fail");
+        assert!(matches!(err, PoolBuildError::Config(ConfigError::MissingField("host"))));
+
+        let err = anyhow::Error::from(err);
+        assert!(err.chain().any(|cause| cause.is::<ConfigError>()));
+    }
+    /// Test that the driver's unknown-plugin handshake error maps to `UnsupportedAuthPlugin`,
+    /// while other errors stay `Connect` errors.
+    #[test]
+    fn test_unsupported_auth_plugin_mapping() {
+        let err = sqlx::Error::Protocol(
+            "unknown authentication plugin: authentication_ldap_sasl_client".into(),
+        );
+
+        let err = PoolBuildError::from_connect_error(err);
+
+        match &err {
+            PoolBuildError::UnsupportedAuthPlugin(plugin) => {
+                assert_eq!(plugin, "authentication_ldap_sasl_client")
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
+        assert!(err.to_string().contains("mysql_clear_password"));
+        assert!(matches!(
+            PoolBuildError::from_connect_error(sqlx::Error::PoolTimedOut),
+            PoolBuildError::Connect(_)
+        ));
+    }
+
+    /// Database error returned by a fake server, for message-based hints.
+    #[derive(Debug)]
+    struct FakeDatabaseError(&'static str);
+
+    impl fmt::Display for FakeDatabaseError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str(self.0)
+        }
+    }
+
+    impl std::error::Error for FakeDatabaseError {}
+
+    impl sqlx::error::DatabaseError for FakeDatabaseError {
+        fn message(&self) -> &str {
+            self.0
+        }
+
+        fn as_error(&self) -> &(dyn std::error::Error + Send + Sync + 'static) {
+            self
+        }
+
+        fn as_error_mut(&mut self) -> &mut (dyn std::error::Error + Send + Sync + 'static) {
+            self
+        }
+
+        fn into_error(self: Box<Self>) -> Box<dyn std::error::Error + Send + Sync + 'static> {
+            self
+        }
+
+        fn kind(&self) -> sqlx::error::ErrorKind {
+            sqlx::error::ErrorKind::Other
+        }
+    }
+
+    /// Test that each `ConfigError` variant's message carries the expected hint.
+    #[test]
+    fn test_config_error_hints() {
+        let cases = [
+            (
+                ConfigError::Io {
+                    path: "/etc/tidb.toml".into(),
+                    source: io::Error::from(io::ErrorKind::NotFound),
+                },
+                "check that the file exists",
+            ),
+            (ConfigError::MissingField("username"), "set `username`"),
+            (ConfigError::Unsupported("x".into()), "use a supported value"),
+            (ConfigError::SslCaRequired, "set `sslCa` to the CA certificate path"),
+            (
+                ConfigError::Invalid {
+                    field: "sslCa",
+                    reason: "file /etc/ssl/missing.pem does not exist".into(),
+                },
+                "relative paths are resolved",
+            ),
+        ];
+
+        for (err, hint) in cases {
+            let message = err.to_string();
+            assert!(message.contains("(hint: ") && message.contains(hint), "{}", message);
+        }
+
+        let err = ConfigError::Invalid {
+            field: "sslCa",
+            reason: "file /etc/ssl/missing.pem does not exist".into(),
+        };
+        assert!(err.to_string().contains("/etc/ssl/missing.pem"));
+        let err = ConfigError::Invalid {
+            field: "sslMode",
+            reason: "bad".into(),
+        };
+        assert!(!err.to_string().contains("hint"));
+    }
+
+    /// Test that each `PoolBuildError` variant's message carries the expected hint.
+    #[test]
+    fn test_pool_build_error_hints() {
+        let database_error = |message| {
+            PoolBuildError::Connect(sqlx::Error::Database(Box::new(FakeDatabaseError(message))))
+        };
+        let cases = [
+            (
+                database_error("Access denied for user 'app'@'%' (using password: YES)"),
+                "check `username` and `password`",
+            ),
+            (
+                database_error("Access denied for user 'app'@'%' to database 'orders'"),
+                "grant the user access to `databaseName`",
+            ),
+            (database_error("Unknown database 'orders'"), "check `databaseName`"),
+            (
+                database_error("Unknown or incorrect time zone: 'Mars/Olympus_Mons'"),
+                "check `timeZone`",
+            ),
+            (
+                PoolBuildError::Connect(sqlx::Error::Io(io::ErrorKind::ConnectionRefused.into())),
+                "check `host` and `port`",
+            ),
+            (
+                PoolBuildError::Connect(sqlx::Error::Io(io::ErrorKind::NotFound.into())),
+                "`sslCa`, `sslClientCert` and `sslClientKey` files exist",
+            ),
+            (
+                PoolBuildError::Connect(sqlx::Error::Tls("bad certificate".into())),
+                "check `sslMode`",
+            ),
+            (
+                PoolBuildError::Connect(sqlx::Error::PoolTimedOut),
+                "raise `acquireTimeout`",
+            ),
+            (
+                PoolBuildError::NotReady {
+                    timeout: Duration::from_secs(5),
+                    last_error: None,
+                },
+                "raise the readiness timeout",
+            ),
+            (
+                PoolBuildError::ServerVersion {
+                    required: "7.1.0".into(),
+                    found: "6.5.0".into(),
+                },
+                "lower `minServerVersion`",
+            ),
+            (
+                PoolBuildError::UnsupportedAuthPlugin("authentication_ldap_sasl".into()),
+                "ALTER USER",
+            ),
+            (
+                PoolBuildError::Config(ConfigError::SslCaRequired),
+                "set `sslCa`",
+            ),
+        ];
+
+        for (err, hint) in cases {
+            let message = err.to_string();
+            assert!(message.contains("(hint: ") && message.contains(hint), "{}", message);
+            assert_eq!(message.matches("(hint:").count(), 1, "{}", message);
+        }
+    }
+
+    /// Test that statement warnings convert into a database error listing every warning.
+    #[test]
+    fn test_statement_warnings_into_database_error() {
+        let warnings = StatementWarnings::new(vec![
+            "Warning 1292: Truncated incorrect DOUBLE value: '1abc'".to_owned(),
+            "Warning 1265: Data truncated for column 'a' at row 1".to_owned(),
+        ]);
+
+        let err = sqlx::Error::from(warnings);
+
+        let sqlx::Error::Database(err) = err else {
+            panic!("unexpected error: {:?}", err);
+        };
+        assert_eq!(
+            err.message(),
+            "statement produced 2 warning(s): \
+             Warning 1292: Truncated incorrect DOUBLE value: '1abc'; \
+             Warning 1265: Data truncated for column 'a' at row 1"
+        );
+        assert_eq!(err.downcast_ref::<StatementWarnings>().warnings().len(), 2);
+    }
+}
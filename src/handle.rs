@@ -0,0 +1,210 @@
+//! A pool paired with the configuration it was built from.
+
+use std::sync::Arc;
+
+use futures_util::future::BoxFuture;
+use sqlx::{mysql::MySqlArguments, pool::PoolConnection, Error, MySql, MySqlPool, Transaction};
+use tracing::Instrument;
+
+use crate::{
+    acquire::SlowAcquireLog, config::TiDBConfig, count::Count, query::delete_rows,
+    tables_family::TableName, transaction::with_transaction,
+};
+
+/// A TiDB connection pool together with the configuration it was built from.
+///
+/// Cloning is cheap: clones share the same pool, configuration and slow-acquire counters.
+#[derive(Clone, Debug)]
+pub struct TiDBHandle {
+    pool: MySqlPool,
+    config: Arc<TiDBConfig>,
+    name: Arc<str>,
+    slow_acquire: Option<Arc<SlowAcquireLog>>,
+}
+
+impl TiDBHandle {
+    /// Pairs an already built pool with its configuration.
+    ///
+    /// The handle is named after the configured [`pool_name`](crate::PoolOptions::pool_name), or
+    /// the database if none is set; see [`with_name`][Self::with_name].
+    pub fn new(pool: MySqlPool, config: TiDBConfig) -> Self {
+        let slow_acquire = SlowAcquireLog::from_options(&config.pool_options).map(Arc::new);
+        TiDBHandle {
+            pool,
+            name: config.pool_name().into(),
+            config: Arc::new(config),
+            slow_acquire,
+        }
+    }
+
+    /// Sets the name identifying this pool in tracing spans, e.g. `"orders-primary"`.
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into().into();
+        self
+    }
+
+    /// The name identifying this pool in tracing spans.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The underlying connection pool.
+    pub fn pool(&self) -> &MySqlPool {
+        &self.pool
+    }
+
+    /// The configuration the pool was built from.
+    pub fn config(&self) -> &TiDBConfig {
+        &self.config
+    }
+
+    /// Acquires a connection, applying the configured slow-acquire log sampling.
+    pub async fn acquire(&self) -> Result<PoolConnection<MySql>, Error> {
+        match &self.slow_acquire {
+            Some(slow_acquire) => slow_acquire.acquire(&self.pool).await,
+            None => self.pool.acquire().await,
+        }
+    }
+
+    /// Runs `f` inside a transaction on this handle's pool; see [`with_transaction`].
+    ///
+    /// The transaction runs in a `tidb_transaction` tracing span whose `pool` field holds the
+    /// handle's [`name`][Self::name].
+    ///
+    /// ## Example:
+    /// ```rust,ignore
+    /// let id: u64 = handle
+    ///     .transaction(|tx| {
+    ///         Box::pin(async move {
+    ///             let result = sqlx::query("INSERT INTO orders (total) VALUES (?)")
+    ///                 .bind(42)
+    ///                 .execute(&mut **tx)
+    ///                 .await?;
+    ///             Ok(result.last_insert_id())
+    ///         })
+    ///     })
+    ///     .await?;
+    /// ```
+    pub async fn transaction<F, T>(&self, f: F) -> Result<T, Error>
+    where
+        F: for<'c> FnOnce(&'c mut Transaction<'static, MySql>) -> BoxFuture<'c, Result<T, Error>>,
+    {
+        let span = info_span!("tidb_transaction", pool = %self.name);
+        with_transaction(&self.pool, f).instrument(span).await
+    }
+
+    /// Deletes the rows of `table` matching `where_clause`; see [`delete_where`].
+    ///
+    /// If [`treat_warnings_as_errors`](crate::PoolOptions::treat_warnings_as_errors) is set,
+    /// the delete is rolled back and fails with a [`StatementWarnings`](crate::StatementWarnings)
+    /// error when the server reports any warning.
+    ///
+    /// [`delete_where`]: crate::delete_where
+    pub async fn delete_where(
+        &self,
+        table: &TableName,
+        where_clause: &str,
+        args: MySqlArguments,
+    ) -> Result<Count, Error> {
+        let treat_warnings_as_errors = self.config.pool_options.treat_warnings_as_errors;
+        delete_rows(&self.pool, table, where_clause, args, treat_warnings_as_errors).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{build_pool_from_config, test_support::integration_config};
+
+    /// Test committing and rolling back through the handle.
+    #[tokio::test]
+    #[ignore = "requires a running TiDB instance"]
+    async fn test_handle_transaction_commit_and_rollback() {
+        let config = integration_config();
+        let pool = build_pool_from_config(config.clone())
+            .await
+            .expect("Failed to build pool");
+        let handle = TiDBHandle::new(pool, config).with_name("test");
+        sqlx::raw_sql(
+            "DROP TABLE IF EXISTS tidb_pool_handle_tx;
+             CREATE TABLE tidb_pool_handle_tx (id INT PRIMARY KEY);",
+        )
+        .execute(handle.pool())
+        .await
+        .expect("Failed to create table");
+
+        handle
+            .transaction(|tx| {
+                Box::pin(async move {
+                    sqlx::query("INSERT INTO tidb_pool_handle_tx VALUES (1)")
+                        .execute(&mut **tx)
+                        .await?;
+                    Ok(())
+                })
+            })
+            .await
+            .expect("Transaction should commit");
+
+        let result: Result<(), Error> = handle
+            .transaction(|tx| {
+                Box::pin(async move {
+                    sqlx::query("INSERT INTO tidb_pool_handle_tx VALUES (2)")
+                        .execute(&mut **tx)
+                        .await?;
+                    Err(Error::RowNotFound)
+                })
+            })
+            .await;
+        assert!(matches!(result, Err(Error::RowNotFound)));
+
+        let ids: Vec<i32> = sqlx::query_scalar("SELECT id FROM tidb_pool_handle_tx")
+            .fetch_all(handle.pool())
+            .await
+            .expect("Failed to read rows");
+        assert_eq!(ids, vec![1]);
+    }
+
+    /// Test that a delete producing a warning is rolled back when warnings are errors.
+    #[tokio::test]
+    #[ignore = "requires a running TiDB instance"]
+    async fn test_handle_delete_where_treats_warnings_as_errors() {
+        use sqlx::Arguments;
+
+        let mut config = integration_config();
+        config.pool_options.treat_warnings_as_errors = true;
+        let pool = build_pool_from_config(config.clone())
+            .await
+            .expect("Failed to build pool");
+        let handle = TiDBHandle::new(pool, config);
+        sqlx::raw_sql(
+            "DROP TABLE IF EXISTS tidb_pool_handle_warnings;
+             CREATE TABLE tidb_pool_handle_warnings (id INT PRIMARY KEY);
+             INSERT INTO tidb_pool_handle_warnings VALUES (1), (2);",
+        )
+        .execute(handle.pool())
+        .await
+        .expect("Failed to create table");
+        let table = TableName::new("tidb_pool_handle_warnings").unwrap();
+
+        // Comparing an integer column with `'1abc'` truncates the string, with a warning
+        let mut args = MySqlArguments::default();
+        args.add("1abc").unwrap();
+        let err = handle
+            .delete_where(&table, "id = ?", args)
+            .await
+            .expect_err("Delete with a warning should fail");
+        let Error::Database(err) = err else {
+            panic!("unexpected error: {:?}", err);
+        };
+        let warnings = err
+            .try_downcast_ref::<crate::StatementWarnings>()
+            .expect("Expected statement warnings");
+        assert!(!warnings.warnings().is_empty());
+        assert_eq!(*crate::count_rows(handle.pool(), &table).await.unwrap(), 2);
+
+        let mut args = MySqlArguments::default();
+        args.add(1).unwrap();
+        let deleted = handle.delete_where(&table, "id = ?", args).await.unwrap();
+        assert_eq!(*deleted, 1);
+    }
+}
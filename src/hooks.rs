@@ -0,0 +1,50 @@
+//! Callbacks invoked by the pool for custom metrics and instrumentation.
+
+use std::{fmt, sync::Arc, time::Duration};
+
+/// Callback receiving a duration measured on a connection.
+pub type DurationCallback = Arc<dyn Fn(Duration) + Send + Sync>;
+
+/// Callbacks invoked by pools built with [`build_pool_with_hooks`](crate::build_pool_with_hooks).
+///
+/// Unlike the settings in [`TiDBConfig`](crate::TiDBConfig), hooks are code and cannot be loaded
+/// from a configuration file.
+///
+/// ## Example:
+/// ```rust,ignore
+/// let hooks = PoolHooks {
+///     on_connection_initialized: Some(Arc::new(|elapsed| {
+///         histogram!("tidb_connection_init").record(elapsed)
+///     })),
+/// };
+/// let pool = build_pool_with_hooks(config, hooks).await?;
+/// ```
+#[derive(Clone, Default)]
+pub struct PoolHooks {
+    /// Invoked after each new connection is established and initialized, with the time spent
+    /// initializing it: running the session statements and looking up its id for the query
+    /// watchdog.
+    ///
+    /// This is not the connect latency: sqlx opens pooled connections internally and does not
+    /// expose when the handshake started, so the handshake itself is not included. Measuring
+    /// costs no extra round trip.
+    pub on_connection_initialized: Option<DurationCallback>,
+}
+
+impl PoolHooks {
+    /// Returns `true` if no hook is set.
+    pub fn is_empty(&self) -> bool {
+        self.on_connection_initialized.is_none()
+    }
+}
+
+impl fmt::Debug for PoolHooks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PoolHooks")
+            .field(
+                "on_connection_initialized",
+                &self.on_connection_initialized.as_ref().map(|_| "Fn(Duration)"),
+            )
+            .finish()
+    }
+}
@@ -0,0 +1,207 @@
+//! Minimal HTTP endpoint serving pool metrics in the Prometheus text format.
+
+use std::{fmt::Write as _, io, net::SocketAddr, time::Duration};
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+use tokio_util::sync::CancellationToken;
+
+use crate::{handle::TiDBHandle, stats::PoolStats};
+
+/// Maximum size of a request head; larger requests are rejected.
+const MAX_REQUEST_HEAD: usize = 8 * 1024;
+
+/// Time allowed for a client to send its request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Serves the metrics of `handle`'s pool on `GET /metrics` until `shutdown` is cancelled.
+///
+/// The endpoint reports the following gauges in the Prometheus text format, labelled with the
+/// handle's [`name`](TiDBHandle::name) as `pool`:
+///
+/// * `tidb_pool_connections`: connections held by the pool (idle and in use).
+/// * `tidb_pool_idle_connections`: idle connections.
+/// * `tidb_pool_in_use_connections`: connections checked out of the pool.
+/// * `tidb_pool_max_connections`: the configured `max_connections`.
+/// * `tidb_pool_utilization`: fraction of `max_connections` in use, see
+///   [`utilization`](crate::utilization).
+///
+/// Each request is answered on its own task and the connection is closed afterwards. Any other
+/// path is answered with `404` and any other method with `405`. Returns an error only if `addr`
+/// cannot be bound.
+///
+/// ## Example:
+/// ```rust,ignore
+/// let shutdown = CancellationToken::new();
+/// tokio::spawn(serve_metrics(handle.clone(), "0.0.0.0:9187".parse()?, shutdown.clone()));
+/// // ... on shutdown ...
+/// shutdown.cancel();
+/// ```
+pub async fn serve_metrics(
+    handle: TiDBHandle,
+    addr: SocketAddr,
+    shutdown: CancellationToken,
+) -> io::Result<()> {
+    let listener = TcpListener::bind(addr).await?;
+    info!("Serving TiDB pool metrics on http://{}/metrics", listener.local_addr()?);
+
+    while let Some(accepted) = shutdown.run_until_cancelled(listener.accept()).await {
+        match accepted {
+            Ok((stream, peer)) => {
+                let handle = handle.clone();
+                tokio::spawn(async move {
+                    if let Err(err) = respond(stream, &handle).await {
+                        debug!("Failed to answer metrics request from {}: {}", peer, err);
+                    }
+                });
+            }
+            Err(err) => warn!("Failed to accept metrics connection: {}", err),
+        }
+    }
+
+    debug!("TiDB pool metrics endpoint stopped");
+    Ok(())
+}
+
+/// Reads one request from `stream` and writes the response.
+async fn respond(mut stream: TcpStream, handle: &TiDBHandle) -> io::Result<()> {
+    let head = tokio::time::timeout(REQUEST_TIMEOUT, read_request_head(&mut stream))
+        .await
+        .map_err(|_| io::Error::from(io::ErrorKind::TimedOut))??;
+
+    let mut request_line = head.lines().next().unwrap_or_default().split_whitespace();
+    let (status, body) = match (request_line.next(), request_line.next()) {
+        (Some("GET"), Some("/metrics")) => ("200 OK", render_metrics(handle)),
+        (Some("GET"), _) => ("404 Not Found", "not found\n".to_owned()),
+        _ => ("405 Method Not Allowed", "method not allowed\n".to_owned()),
+    };
+
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\n\
+         Connection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+/// Reads the request line and headers, up to the first empty line.
+async fn read_request_head(stream: &mut TcpStream) -> io::Result<String> {
+    let mut head = Vec::new();
+    let mut buf = [0; 1024];
+    while !head.windows(4).any(|window| window == b"\r\n\r\n") {
+        let read = stream.read(&mut buf).await?;
+        if read == 0 {
+            break;
+        }
+        head.extend_from_slice(&buf[..read]);
+        if head.len() > MAX_REQUEST_HEAD {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "request head too large"));
+        }
+    }
+    Ok(String::from_utf8_lossy(&head).into_owned())
+}
+
+/// Renders the pool gauges in the Prometheus text exposition format.
+fn render_metrics(handle: &TiDBHandle) -> String {
+    let stats = PoolStats::from_pool(handle.pool());
+    let max = handle.config().pool_options.max_connections;
+    let pool = escape_label_value(handle.name());
+
+    let gauges: [(&str, &str, f64); 5] = [
+        (
+            "tidb_pool_connections",
+            "Connections held by the pool, idle and in use.",
+            f64::from(stats.size),
+        ),
+        ("tidb_pool_idle_connections", "Idle connections.", f64::from(stats.num_idle)),
+        (
+            "tidb_pool_in_use_connections",
+            "Connections checked out of the pool.",
+            f64::from(stats.in_use()),
+        ),
+        ("tidb_pool_max_connections", "Configured maximum connections.", f64::from(max)),
+        (
+            "tidb_pool_utilization",
+            "Fraction of the maximum connections in use.",
+            f64::from(stats.utilization(max)),
+        ),
+    ];
+
+    let mut body = String::new();
+    for (name, help, value) in gauges {
+        let _ = writeln!(body, "# HELP {} {}", name, help);
+        let _ = writeln!(body, "# TYPE {} gauge", name);
+        let _ = writeln!(body, "{}{{pool=\"{}\"}} {}", name, pool, value);
+    }
+    body
+}
+
+/// Escapes a Prometheus label value.
+fn escape_label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{build_pool_from_config, test_support::integration_config};
+
+    /// Test that label values are escaped.
+    #[test]
+    fn test_escape_label_value() {
+        assert_eq!(escape_label_value("a\"b\\c\nd"), "a\\\"b\\\\c\\nd");
+    }
+
+    /// Test that `GET /metrics` returns every metric and that cancelling stops the server.
+    #[tokio::test]
+    async fn test_serve_metrics() {
+        let mut config = integration_config();
+        config.pool_options.is_lazy = true;
+        let pool = build_pool_from_config(config.clone())
+            .await
+            .expect("Failed to build pool");
+        let handle = TiDBHandle::new(pool, config).with_name("orders");
+
+        let addr = std::net::TcpListener::bind("127.0.0.1:0")
+            .and_then(|listener| listener.local_addr())
+            .unwrap();
+        let shutdown = CancellationToken::new();
+        let server = tokio::spawn(serve_metrics(handle, addr, shutdown.clone()));
+
+        let mut stream = loop {
+            match TcpStream::connect(addr).await {
+                Ok(stream) => break stream,
+                Err(_) => tokio::time::sleep(Duration::from_millis(10)).await,
+            }
+        };
+        stream
+            .write_all(b"GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{}", response);
+        for name in [
+            "tidb_pool_connections",
+            "tidb_pool_idle_connections",
+            "tidb_pool_in_use_connections",
+            "tidb_pool_max_connections",
+            "tidb_pool_utilization",
+        ] {
+            assert!(response.contains(&format!("# TYPE {} gauge", name)), "{}", response);
+        }
+        assert!(response.contains("tidb_pool_max_connections{pool=\"orders\"} 10"));
+
+        shutdown.cancel();
+        server.await.unwrap().expect("Server failed");
+    }
+}
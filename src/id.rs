@@ -1,7 +1,32 @@
-use std::ops::Deref;
+use std::{fmt, ops::Deref};
 
-#[derive(sqlx::FromRow, Debug)]
+use sqlx::{
+    error::BoxDynError,
+    mysql::{MySqlTypeInfo, MySqlValueRef},
+    Decode, MySql, Type,
+};
+
+/// An unsigned integer id.
+///
+/// Decodes from a row with a single column, and as a column of its own, so rows such as
+/// `SELECT id, COUNT(*) ...` decode into `(ID, Count)` tuples. Serializes as the bare integer,
+/// e.g. `42`; see [`id_as_string`] to serialize it as a string instead.
+#[derive(
+    sqlx::FromRow,
+    sqlx::Type,
+    Serialize,
+    Deserialize,
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+)]
 #[sqlx(transparent)]
+#[serde(transparent)]
 pub struct ID(pub u64);
 
 /// Enable `Deref` coercion `ID`.
@@ -9,3 +34,323 @@ impl Deref for ID {
     type Target = u64;
     fn deref(&self) -> &Self::Target { &self.0 }
 }
+
+impl From<u64> for ID {
+    fn from(value: u64) -> Self { ID(value) }
+}
+
+impl From<ID> for u64 {
+    fn from(value: ID) -> Self { value.0 }
+}
+
+/// Prints the bare decimal number, e.g. `123`, for use in URLs, logs and error messages.
+impl fmt::Display for ID {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { fmt::Display::fmt(&self.0, f) }
+}
+
+/// Formats the id as lowercase hex, e.g. `format!("{:x}", id)`; flags such as `#` and widths
+/// are honored.
+impl fmt::LowerHex for ID {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { fmt::LowerHex::fmt(&self.0, f) }
+}
+
+/// Formats the id as uppercase hex, e.g. `format!("{:X}", id)`.
+impl fmt::UpperHex for ID {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { fmt::UpperHex::fmt(&self.0, f) }
+}
+
+/// Sorts `ids` in ascending order and removes duplicates.
+///
+/// Use it before binding ids into an `IN (...)` clause, so that the same set of ids always
+/// produces the same SQL and parameters, which keeps prepared statements cache-friendly.
+///
+/// ## Example:
+/// ```rust
+/// use tidb_pool::{normalize_ids, ID};
+///
+/// assert_eq!(normalize_ids([ID(3), ID(1), ID(3)]), vec![ID(1), ID(3)]);
+/// ```
+pub fn normalize_ids(ids: impl IntoIterator<Item = ID>) -> Vec<ID> {
+    let mut ids: Vec<ID> = ids.into_iter().collect();
+    ids.sort_unstable();
+    ids.dedup();
+    ids
+}
+
+#[cfg(feature = "decimal")]
+impl ID {
+    /// Converts the id into an exact `rust_decimal::Decimal`.
+    pub fn to_decimal(&self) -> rust_decimal::Decimal { rust_decimal::Decimal::from(self.0) }
+}
+
+#[cfg(feature = "decimal")]
+impl From<ID> for rust_decimal::Decimal {
+    fn from(value: ID) -> Self { value.to_decimal() }
+}
+
+/// Serializes an [`ID`] as a decimal string, e.g. `"123"`, for clients such as GraphQL or
+/// JavaScript that expect string ids or cannot represent every `u64` exactly.
+///
+/// Use it with `#[serde(with = "tidb_pool::id_as_string")]` on an `ID` field; the field keeps
+/// whatever name serde gives it. Deserializing accepts a string or a non-negative integer.
+///
+/// ## Example:
+/// ```rust
+/// use tidb_pool::ID;
+///
+/// #[derive(serde::Serialize, serde::Deserialize)]
+/// struct Order {
+///     #[serde(rename = "orderId", with = "tidb_pool::id_as_string")]
+///     id: ID,
+/// }
+///
+/// let json = serde_json::to_string(&Order { id: ID(42) }).unwrap();
+/// assert_eq!(json, r#"{"orderId":"42"}"#);
+/// ```
+pub mod id_as_string {
+    use std::fmt;
+
+    use serde::{de, Deserializer, Serializer};
+
+    use super::ID;
+
+    /// Serializes `id` as a decimal string.
+    pub fn serialize<S: Serializer>(id: &ID, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(id)
+    }
+
+    /// Deserializes an id from a decimal string or a non-negative integer.
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<ID, D::Error> {
+        deserializer.deserialize_any(IdVisitor)
+    }
+
+    struct IdVisitor;
+
+    impl de::Visitor<'_> for IdVisitor {
+        type Value = ID;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("an unsigned integer id or a string holding one")
+        }
+
+        fn visit_u64<E: de::Error>(self, value: u64) -> Result<ID, E> { Ok(ID(value)) }
+
+        fn visit_i64<E: de::Error>(self, value: i64) -> Result<ID, E> {
+            u64::try_from(value)
+                .map(ID)
+                .map_err(|_| E::invalid_value(de::Unexpected::Signed(value), &self))
+        }
+
+        fn visit_str<E: de::Error>(self, value: &str) -> Result<ID, E> {
+            value
+                .parse()
+                .map(ID)
+                .map_err(|_| E::invalid_value(de::Unexpected::Str(value), &self))
+        }
+    }
+}
+
+/// The GraphQL `ID` scalar: written as a string and read from a string or a non-negative
+/// integer, as the GraphQL specification allows.
+///
+/// It uses the name `ID`, so it cannot be registered in the same schema as
+/// `async_graphql::ID`.
+#[cfg(feature = "graphql")]
+#[async_graphql::Scalar(name = "ID")]
+impl async_graphql::ScalarType for ID {
+    fn parse(value: async_graphql::Value) -> async_graphql::InputValueResult<Self> {
+        use async_graphql::{InputValueError, Value};
+
+        let id = match &value {
+            Value::String(text) => text.parse().ok(),
+            Value::Number(number) => number.as_u64(),
+            _ => None,
+        };
+        id.map(ID).ok_or_else(|| InputValueError::expected_type(value))
+    }
+
+    fn to_value(&self) -> async_graphql::Value { async_graphql::Value::String(self.to_string()) }
+}
+
+/// A numeric id stored in a character column, such as a legacy `VARCHAR` primary key.
+///
+/// Use [`ID`] for integer columns. Use `StringId` when the column is textual but always holds
+/// an unsigned decimal integer: the text is parsed when decoding, and decoding fails if the
+/// column contains anything else.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StringId(pub u64);
+
+/// Enable `Deref` coercion `StringId`.
+impl Deref for StringId {
+    type Target = u64;
+    fn deref(&self) -> &Self::Target { &self.0 }
+}
+
+impl From<StringId> for ID {
+    fn from(value: StringId) -> Self { ID(value.0) }
+}
+
+impl Type<MySql> for StringId {
+    fn type_info() -> MySqlTypeInfo { <str as Type<MySql>>::type_info() }
+    fn compatible(ty: &MySqlTypeInfo) -> bool { <str as Type<MySql>>::compatible(ty) }
+}
+
+impl<'r> Decode<'r, MySql> for StringId {
+    fn decode(value: MySqlValueRef<'r>) -> Result<Self, BoxDynError> {
+        let text = <&str as Decode<MySql>>::decode(value)?;
+        let id = text
+            .trim()
+            .parse()
+            .map_err(|err| format!("invalid numeric id `{}`: {}", text, err))?;
+        Ok(StringId(id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{build_pool_from_config, count::Count, test_support::integration_config};
+
+    /// Test that `Display` prints the bare decimal number.
+    #[test]
+    fn test_id_display() {
+        assert_eq!(format!("{}", ID(123)), "123");
+        assert_eq!(format!("/orders/{}", ID(u64::MAX)), "/orders/18446744073709551615");
+        assert_eq!(ID(0).to_string(), "0");
+    }
+
+    /// Test hex formatting of ids.
+    #[test]
+    fn test_id_hex_formatting() {
+        assert_eq!(format!("{:x}", ID(255)), "ff");
+        assert_eq!(format!("{:X}", ID(255)), "FF");
+        assert_eq!(format!("{:#x}", ID(4096)), "0x1000");
+        assert_eq!(format!("{:08x}", ID(48879)), "0000beef");
+        assert_eq!(format!("{:X}", ID(u64::MAX)), "FFFFFFFFFFFFFFFF");
+        assert_eq!(format!("{:x}", ID(0)), "0");
+    }
+
+    /// Test converting between `ID` and `u64` in both directions.
+    #[test]
+    fn test_id_from_into_u64() {
+        let id: ID = 42.into();
+        assert_eq!(id, ID(42));
+        assert_eq!(ID::from(u64::MAX), ID(u64::MAX));
+
+        let n: u64 = id.into();
+        assert_eq!(n, 42);
+        assert_eq!(u64::from(ID(0)), 0);
+    }
+
+    /// Test that an id round-trips through JSON as the bare integer.
+    #[test]
+    fn test_id_serde_transparent() {
+        assert_eq!(serde_json::to_string(&ID(42)).unwrap(), "42");
+        assert_eq!(serde_json::to_string(&ID(u64::MAX)).unwrap(), "18446744073709551615");
+        assert_eq!(serde_json::from_str::<ID>("42").unwrap(), ID(42));
+        assert_eq!(serde_json::from_str::<Vec<ID>>("[1, 2]").unwrap(), vec![ID(1), ID(2)]);
+        assert!(serde_json::from_str::<ID>("-1").is_err());
+    }
+
+    /// Test serializing an id field as a string under a renamed key, and reading it back.
+    #[test]
+    fn test_id_as_string() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Node {
+            #[serde(rename = "nodeId", with = "id_as_string")]
+            id: ID,
+        }
+
+        let node = Node { id: ID(u64::MAX) };
+        let json = serde_json::to_string(&node).unwrap();
+        assert_eq!(json, r#"{"nodeId":"18446744073709551615"}"#);
+        assert_eq!(serde_json::from_str::<Node>(&json).unwrap(), node);
+
+        let node: Node = serde_json::from_str(r#"{"nodeId":7}"#).unwrap();
+        assert_eq!(node.id, ID(7));
+        assert!(serde_json::from_str::<Node>(r#"{"nodeId":"abc"}"#).is_err());
+        assert!(serde_json::from_str::<Node>(r#"{"nodeId":-1}"#).is_err());
+    }
+
+    /// Test the GraphQL scalar round-trip, directly and through a schema.
+    #[cfg(feature = "graphql")]
+    #[tokio::test]
+    async fn test_id_graphql_scalar() {
+        use async_graphql::{EmptyMutation, EmptySubscription, Object, ScalarType, Schema, Value};
+
+        assert_eq!(ID(42).to_value(), Value::String("42".into()));
+        assert_eq!(<ID as ScalarType>::parse(ID(42).to_value()).unwrap(), ID(42));
+        assert_eq!(<ID as ScalarType>::parse(Value::from(7)).unwrap(), ID(7));
+        assert!(<ID as ScalarType>::parse(Value::from(-1)).is_err());
+        assert!(<ID as ScalarType>::parse(Value::String("abc".into())).is_err());
+
+        struct Query;
+
+        #[Object]
+        impl Query {
+            async fn next(&self, id: ID) -> ID { ID(*id + 1) }
+        }
+
+        let schema = Schema::new(Query, EmptyMutation, EmptySubscription);
+        let response = schema.execute(r#"{ next(id: "41") }"#).await;
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+        assert_eq!(response.data.into_json().unwrap(), serde_json::json!({ "next": "42" }));
+    }
+
+    /// Test that ids are sorted and deduplicated.
+    #[test]
+    fn test_normalize_ids() {
+        let ids = [ID(42), ID(7), ID(42), ID(u64::MAX), ID(0), ID(7), ID(7)];
+        assert_eq!(normalize_ids(ids), vec![ID(0), ID(7), ID(42), ID(u64::MAX)]);
+
+        assert_eq!(normalize_ids([ID(3), ID(2), ID(1)]), vec![ID(1), ID(2), ID(3)]);
+        assert!(normalize_ids(Vec::new()).is_empty());
+    }
+
+    /// Test decoding a two-column row into an `(ID, Count)` tuple.
+    #[tokio::test]
+    #[ignore = "requires a running TiDB instance"]
+    async fn test_decode_id_count_tuple() {
+        let pool = build_pool_from_config(integration_config())
+            .await
+            .expect("Failed to build pool");
+        sqlx::raw_sql(
+            "DROP TABLE IF EXISTS tidb_pool_id_count;
+             CREATE TABLE tidb_pool_id_count (id BIGINT UNSIGNED, tag VARCHAR(8));
+             INSERT INTO tidb_pool_id_count VALUES (1, 'a'), (1, 'b'), (2, 'c');",
+        )
+        .execute(&pool)
+        .await
+        .expect("Failed to create table");
+
+        let rows: Vec<(ID, Count)> = sqlx::query_as(
+            "SELECT id, COUNT(*) AS cnt FROM tidb_pool_id_count GROUP BY id ORDER BY id",
+        )
+        .fetch_all(&pool)
+        .await
+        .expect("Failed to decode rows");
+
+        let rows: Vec<(u64, i64)> = rows.into_iter().map(|(id, cnt)| (*id, *cnt)).collect();
+        assert_eq!(rows, vec![(1, 2), (2, 1)]);
+    }
+
+    /// Test decoding numeric and non-numeric `VARCHAR` ids.
+    #[tokio::test]
+    #[ignore = "requires a running TiDB instance"]
+    async fn test_string_id_decodes_varchar() {
+        let pool = build_pool_from_config(integration_config())
+            .await
+            .expect("Failed to build pool");
+
+        let id: StringId = sqlx::query_scalar("SELECT CAST('00042' AS CHAR(16))")
+            .fetch_one(&pool)
+            .await
+            .expect("Failed to decode id");
+        assert_eq!(*id, 42);
+
+        let invalid = sqlx::query_scalar::<_, StringId>("SELECT CAST('abc' AS CHAR(16))")
+            .fetch_one(&pool)
+            .await;
+        assert!(invalid.is_err());
+    }
+}
@@ -3,14 +3,66 @@ extern crate serde;
 #[macro_use]
 extern crate tracing;
 
-pub use config::TiDBConfig;
+pub use acquire::{
+    acquire_cancellable, acquire_many, acquire_tagged, is_acquire_cancelled, with_connection,
+    SlowAcquireLog, MAX_TENANT_TAG_LEN,
+};
+pub use builder::TiDBConfigBuilder;
+pub use config::{
+    AddressFamily, Config, ConfigWarning, InvalidPoolSizes, PoolOptions, RedactedConfig,
+    SslMode, TiDBConfig, TlsVersion,
+};
 pub use count::Count;
-pub use id::ID;
+pub use credentials::{build_pool_with_credentials, CredentialPool, CredentialProvider};
+pub use error::{
+    unsupported_auth_plugin, AcquireCancelled, ConfigError, MissingWhereClause, PoolBuildError,
+    StatementWarnings,
+};
+pub use handle::TiDBHandle;
+pub use hooks::{DurationCallback, PoolHooks};
+#[cfg(feature = "http-metrics")]
+pub use http_metrics::serve_metrics;
+pub use id::{id_as_string, normalize_ids, StringId, ID};
+pub use partial::{PartialPoolOptions, PartialTiDBConfig};
 pub use pool::*;
+pub use preflight::preflight;
+pub use query::*;
+pub use quota::{try_consume_quota, QUOTA_TABLE};
+pub use read_write::{query_all_replicas, ReadWritePool};
+pub use retry::*;
+pub use session::{connection_id, with_stale_read, SessionProfile, TiDBSessionFlags};
+pub use stats::{utilization, PoolStats, PoolStatsDelta};
 pub use tables_family::*;
+pub use transaction::with_transaction;
+#[cfg(feature = "tunnel")]
+pub use tunnel::{build_pool_via_tunnel, SshTunnel, SshTunnelConfig, TunneledPool};
+pub use version::ServerVersion;
 
+mod acquire;
+mod builder;
 mod config;
 mod count;
+mod credentials;
+mod error;
+mod handle;
+mod hooks;
+#[cfg(feature = "http-metrics")]
+mod http_metrics;
 mod id;
+mod partial;
 mod pool;
+mod preflight;
+mod query;
+mod quota;
+mod read_write;
+mod retry;
+mod session;
+mod stats;
 mod tables_family;
+#[cfg(test)]
+mod test_support;
+mod transaction;
+#[cfg(feature = "tunnel")]
+mod tunnel;
+mod version;
+mod watchdog;
@@ -0,0 +1,390 @@
+//! Partial configurations applied on top of an existing [`TiDBConfig`], e.g. on hot reload.
+
+use std::fmt;
+
+use crate::{
+    config::{AddressFamily, InvalidPoolSizes, PoolOptions, SslMode, TiDBConfig, TlsVersion},
+    session::TiDBSessionFlags,
+};
+
+/// A [`TiDBConfig`] update in which every field is optional.
+///
+/// Applied with [`TiDBConfig::merge`]: fields that are `Some` replace the corresponding value,
+/// while `None` fields leave it untouched. Fields that are optional in `TiDBConfig` can be set
+/// but not cleared through a partial update.
+///
+/// Deserializes from the same camelCase keys as `TiDBConfig`, so an update can be read from a
+/// TOML fragment.
+///
+/// # Example (TOML)
+/// ```toml
+/// [pool_options]
+/// maxConnections = 50
+/// minConnections = 10
+/// ```
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PartialTiDBConfig {
+    /// See [`TiDBConfig::host`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>,
+    /// See [`TiDBConfig::port`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+    /// See [`TiDBConfig::socket`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub socket: Option<String>,
+    /// See [`TiDBConfig::username`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    /// See [`TiDBConfig::password`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    /// See [`TiDBConfig::database_name`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub database_name: Option<String>,
+    /// See [`TiDBConfig::pool_options`].
+    #[serde(rename = "pool_options", default)]
+    pub pool_options: PartialPoolOptions,
+    /// See [`TiDBConfig::ssl_ca`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ssl_ca: Option<String>,
+    /// See [`TiDBConfig::ssl_mode`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ssl_mode: Option<SslMode>,
+    /// See [`TiDBConfig::ssl_client_cert`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ssl_client_cert: Option<String>,
+    /// See [`TiDBConfig::ssl_client_key`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ssl_client_key: Option<String>,
+    /// See [`TiDBConfig::require_ssl`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub require_ssl: Option<bool>,
+    /// See [`TiDBConfig::allow_public_key_retrieval`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allow_public_key_retrieval: Option<bool>,
+    /// See [`TiDBConfig::min_tls_version`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_tls_version: Option<TlsVersion>,
+    /// See [`TiDBConfig::auth_plugin`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auth_plugin: Option<String>,
+    /// See [`TiDBConfig::comparison_safe`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comparison_safe: Option<bool>,
+    /// See [`TiDBConfig::charset`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub charset: Option<String>,
+    /// See [`TiDBConfig::collation`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub collation: Option<String>,
+    /// See [`TiDBConfig::address_family`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub address_family: Option<AddressFamily>,
+    /// See [`TiDBConfig::session_flags`]. Replaces all flags at once.
+    #[serde(rename = "session_flags", skip_serializing_if = "Option::is_none")]
+    pub session_flags: Option<TiDBSessionFlags>,
+    /// See [`TiDBConfig::init_command`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub init_command: Option<String>,
+    /// See [`TiDBConfig::group_concat_max_len`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group_concat_max_len: Option<u64>,
+    /// See [`TiDBConfig::time_zone`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_zone: Option<String>,
+    /// See [`TiDBConfig::sql_mode`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sql_mode: Option<String>,
+    /// See [`TiDBConfig::min_server_version`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_server_version: Option<String>,
+    /// See [`TiDBConfig::validate_on_build`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub validate_on_build: Option<bool>,
+}
+
+/// A [`PoolOptions`] update in which every field is optional; see [`PartialTiDBConfig`].
+#[derive(Clone, Default, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct PartialPoolOptions {
+    /// See [`PoolOptions::max_connections`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_connections: Option<u32>,
+    /// See [`PoolOptions::min_connections`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_connections: Option<u32>,
+    /// See [`PoolOptions::acquire_timeout`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub acquire_timeout: Option<u64>,
+    /// See [`PoolOptions::connect_timeout`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub connect_timeout: Option<u64>,
+    /// See [`PoolOptions::test_before_acquire`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub test_before_acquire: Option<bool>,
+    /// See [`PoolOptions::idle_timeout`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub idle_timeout: Option<u64>,
+    /// See [`PoolOptions::max_lifetime`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_lifetime: Option<u64>,
+    /// See [`PoolOptions::is_lazy`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_lazy: Option<bool>,
+    /// See [`PoolOptions::statement_cache_capacity`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub statement_cache_capacity: Option<usize>,
+    /// See [`PoolOptions::default_fetch_streaming`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_fetch_streaming: Option<bool>,
+    /// See [`PoolOptions::streaming_max_rows`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub streaming_max_rows: Option<usize>,
+    /// See [`PoolOptions::acquire_slow_threshold_ms`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub acquire_slow_threshold_ms: Option<u64>,
+    /// See [`PoolOptions::acquire_slow_log_sample`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub acquire_slow_log_sample: Option<u32>,
+    /// See [`PoolOptions::expected_replicas`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected_replicas: Option<u32>,
+    /// See [`PoolOptions::server_max_connections`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub server_max_connections: Option<u32>,
+    /// See [`PoolOptions::absolute_max_connections`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub absolute_max_connections: Option<u32>,
+    /// See [`PoolOptions::max_lifetime_jitter_secs`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_lifetime_jitter_secs: Option<u64>,
+    /// See [`PoolOptions::on_invalid_pool_sizes`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on_invalid_pool_sizes: Option<InvalidPoolSizes>,
+    /// See [`PoolOptions::pool_name`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pool_name: Option<String>,
+    /// See [`PoolOptions::verbose_lifecycle`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verbose_lifecycle: Option<bool>,
+    /// See [`PoolOptions::log_statements_level`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub log_statements_level: Option<String>,
+    /// See [`PoolOptions::slow_statements_level`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slow_statements_level: Option<String>,
+    /// See [`PoolOptions::slow_statements_threshold_ms`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slow_statements_threshold_ms: Option<u64>,
+    /// See [`PoolOptions::query_watchdog_secs`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub query_watchdog_secs: Option<u64>,
+    /// See [`PoolOptions::treat_warnings_as_errors`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub treat_warnings_as_errors: Option<bool>,
+    /// See [`PoolOptions::init_sql`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub init_sql: Option<Vec<String>>,
+}
+
+/// Prints the fields like the derived implementation would, except that a set `password` or
+/// `ssl_client_key` is shown as `"***REDACTED***"`.
+impl fmt::Debug for PartialTiDBConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PartialTiDBConfig")
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("socket", &self.socket)
+            .field("username", &self.username)
+            .field("password", &self.password.as_ref().map(|_| "***REDACTED***"))
+            .field("database_name", &self.database_name)
+            .field("pool_options", &self.pool_options)
+            .field("ssl_ca", &self.ssl_ca)
+            .field("ssl_mode", &self.ssl_mode)
+            .field("ssl_client_cert", &self.ssl_client_cert)
+            .field("ssl_client_key", &self.ssl_client_key.as_ref().map(|_| "***REDACTED***"))
+            .field("require_ssl", &self.require_ssl)
+            .field("allow_public_key_retrieval", &self.allow_public_key_retrieval)
+            .field("min_tls_version", &self.min_tls_version)
+            .field("auth_plugin", &self.auth_plugin)
+            .field("comparison_safe", &self.comparison_safe)
+            .field("charset", &self.charset)
+            .field("collation", &self.collation)
+            .field("address_family", &self.address_family)
+            .field("session_flags", &self.session_flags)
+            .field("init_command", &self.init_command)
+            .field("group_concat_max_len", &self.group_concat_max_len)
+            .field("time_zone", &self.time_zone)
+            .field("sql_mode", &self.sql_mode)
+            .field("min_server_version", &self.min_server_version)
+            .field("validate_on_build", &self.validate_on_build)
+            .finish()
+    }
+}
+
+/// Replaces `target` with `value` if it is set.
+fn apply<T: Clone>(target: &mut T, value: &Option<T>) {
+    if let Some(value) = value {
+        *target = value.clone();
+    }
+}
+
+/// Replaces the optional `target` with `value` if it is set.
+fn apply_some<T: Clone>(target: &mut Option<T>, value: &Option<T>) {
+    if value.is_some() {
+        *target = value.clone();
+    }
+}
+
+impl TiDBConfig {
+    /// Applies the fields of `other` that are `Some`, leaving all other fields unchanged.
+    ///
+    /// The result is not validated; call [`validate`][Self::validate] before using it.
+    ///
+    /// ## Example:
+    /// ```rust
+    /// use tidb_pool::{PartialTiDBConfig, TiDBConfig};
+    ///
+    /// let mut config = TiDBConfig::default();
+    /// let update: PartialTiDBConfig = toml::from_str("[pool_options]\nmaxConnections = 50")?;
+    /// config.merge(&update);
+    /// assert_eq!(config.pool_options.max_connections, 50);
+    /// # Ok::<(), toml::de::Error>(())
+    /// ```
+    pub fn merge(&mut self, other: &PartialTiDBConfig) {
+        apply(&mut self.host, &other.host);
+        apply_some(&mut self.port, &other.port);
+        apply_some(&mut self.socket, &other.socket);
+        apply(&mut self.username, &other.username);
+        apply(&mut self.password, &other.password);
+        apply(&mut self.database_name, &other.database_name);
+        self.pool_options.merge(&other.pool_options);
+        apply_some(&mut self.ssl_ca, &other.ssl_ca);
+        apply_some(&mut self.ssl_mode, &other.ssl_mode);
+        apply_some(&mut self.ssl_client_cert, &other.ssl_client_cert);
+        apply_some(&mut self.ssl_client_key, &other.ssl_client_key);
+        apply(&mut self.require_ssl, &other.require_ssl);
+        apply_some(&mut self.allow_public_key_retrieval, &other.allow_public_key_retrieval);
+        apply_some(&mut self.min_tls_version, &other.min_tls_version);
+        apply_some(&mut self.auth_plugin, &other.auth_plugin);
+        apply(&mut self.comparison_safe, &other.comparison_safe);
+        apply_some(&mut self.charset, &other.charset);
+        apply_some(&mut self.collation, &other.collation);
+        apply_some(&mut self.address_family, &other.address_family);
+        apply(&mut self.session_flags, &other.session_flags);
+        apply_some(&mut self.init_command, &other.init_command);
+        apply_some(&mut self.group_concat_max_len, &other.group_concat_max_len);
+        apply_some(&mut self.time_zone, &other.time_zone);
+        apply_some(&mut self.sql_mode, &other.sql_mode);
+        apply_some(&mut self.min_server_version, &other.min_server_version);
+        apply(&mut self.validate_on_build, &other.validate_on_build);
+    }
+}
+
+impl PoolOptions {
+    /// Applies the fields of `other` that are `Some`, leaving all other fields unchanged.
+    pub fn merge(&mut self, other: &PartialPoolOptions) {
+        apply(&mut self.max_connections, &other.max_connections);
+        apply(&mut self.min_connections, &other.min_connections);
+        apply(&mut self.acquire_timeout, &other.acquire_timeout);
+        apply_some(&mut self.connect_timeout, &other.connect_timeout);
+        apply(&mut self.test_before_acquire, &other.test_before_acquire);
+        apply(&mut self.idle_timeout, &other.idle_timeout);
+        apply(&mut self.max_lifetime, &other.max_lifetime);
+        apply(&mut self.is_lazy, &other.is_lazy);
+        apply(&mut self.statement_cache_capacity, &other.statement_cache_capacity);
+        apply(&mut self.default_fetch_streaming, &other.default_fetch_streaming);
+        apply(&mut self.streaming_max_rows, &other.streaming_max_rows);
+        apply_some(&mut self.acquire_slow_threshold_ms, &other.acquire_slow_threshold_ms);
+        apply_some(&mut self.acquire_slow_log_sample, &other.acquire_slow_log_sample);
+        apply_some(&mut self.expected_replicas, &other.expected_replicas);
+        apply_some(&mut self.server_max_connections, &other.server_max_connections);
+        apply_some(&mut self.absolute_max_connections, &other.absolute_max_connections);
+        apply_some(&mut self.max_lifetime_jitter_secs, &other.max_lifetime_jitter_secs);
+        apply(&mut self.on_invalid_pool_sizes, &other.on_invalid_pool_sizes);
+        apply_some(&mut self.pool_name, &other.pool_name);
+        apply(&mut self.verbose_lifecycle, &other.verbose_lifecycle);
+        apply_some(&mut self.log_statements_level, &other.log_statements_level);
+        apply_some(&mut self.slow_statements_level, &other.slow_statements_level);
+        apply_some(&mut self.slow_statements_threshold_ms, &other.slow_statements_threshold_ms);
+        apply_some(&mut self.query_watchdog_secs, &other.query_watchdog_secs);
+        apply(&mut self.treat_warnings_as_errors, &other.treat_warnings_as_errors);
+        apply(&mut self.init_sql, &other.init_sql);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::integration_config;
+
+    /// Test that merging replaces only the provided fields.
+    #[test]
+    fn test_merge_updates_only_provided_fields() {
+        let mut config = integration_config();
+        config.ssl_ca = Some("/etc/ssl/ca.pem".into());
+        let original = config.clone();
+        let update = PartialTiDBConfig {
+            password: Some("rotated".into()),
+            pool_options: PartialPoolOptions {
+                max_connections: Some(50),
+                min_connections: Some(10),
+                ..PartialPoolOptions::default()
+            },
+            ..PartialTiDBConfig::default()
+        };
+
+        config.merge(&update);
+
+        assert_eq!(config.password, "rotated");
+        assert_eq!(config.pool_options.max_connections, 50);
+        assert_eq!(config.pool_options.min_connections, 10);
+        assert_eq!(config.host, original.host);
+        assert_eq!(config.username, original.username);
+        assert_eq!(config.ssl_ca, original.ssl_ca);
+        assert_eq!(config.pool_options.idle_timeout, original.pool_options.idle_timeout);
+        assert_eq!(config.pool_options.is_lazy, original.pool_options.is_lazy);
+    }
+
+    /// Test that an empty update leaves the configuration unchanged.
+    #[test]
+    fn test_merge_empty_update() {
+        let mut config = integration_config();
+        let before = toml::to_string(&config).unwrap();
+
+        config.merge(&PartialTiDBConfig::default());
+
+        assert_eq!(toml::to_string(&config).unwrap(), before);
+    }
+
+    /// Test reading an update from TOML and merging it.
+    #[test]
+    fn test_merge_from_toml() {
+        let update: PartialTiDBConfig = toml::from_str(
+            r#"
+            port = 4001
+            sslMode = "verifyIdentity"
+
+            [pool_options]
+            acquireTimeout = 5
+            "#,
+        )
+        .expect("Failed to parse update");
+        let mut config = integration_config();
+
+        config.merge(&update);
+
+        assert_eq!(config.port, Some(4001));
+        assert_eq!(config.ssl_mode, Some(SslMode::VerifyIdentity));
+        assert_eq!(config.pool_options.acquire_timeout, 5);
+
+        let update = PartialTiDBConfig {
+            password: Some("rotated".into()),
+            ..update
+        };
+        let debug = format!("{:?}", update);
+        assert!(debug.contains("REDACTED") && !debug.contains("rotated"), "{}", debug);
+    }
+}
@@ -1,11 +1,22 @@
-use std::time::Duration;
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
 
+use futures_util::future::{join_all, try_join_all};
 use sqlx::{
-    mysql::{MySqlConnectOptions, MySqlPoolOptions, MySqlSslMode},
-    ConnectOptions, Error, MySqlPool,
+    mysql::{MySqlConnectOptions, MySqlConnection, MySqlPoolOptions},
+    pool::PoolConnection,
+    ConnectOptions, Connection, Error, MySql, MySqlPool,
 };
 
-use crate::config::TiDBConfig;
+use crate::{
+    config::{InvalidPoolSizes, PoolOptions, TiDBConfig},
+    error::{ConfigError, PoolBuildError},
+    hooks::PoolHooks,
+    session::{connection_id, install_session_hook, lifecycle_pool_name, trace_lifecycle},
+    watchdog::{ConnectionIds, QueryWatchdog},
+};
 
 pub type TidbPool = MySqlPool;
 
@@ -33,75 +44,675 @@ pub type TidbPool = MySqlPool;
 /// ```
 #[tracing::instrument(name = "tidb_svc", err, skip(config))]
 pub async fn build_pool_from_config(config: TiDBConfig) -> Result<TidbPool, Error> {
-    info!("Initializing connection pool to TiDB...");
+    build_pool(config, &PoolHooks::default(), false).await
+}
+
+/// Same as [`build_pool_from_config`], but logs the startup details at `debug` level.
+///
+/// Intended for CLI tools that should stay silent unless something goes wrong: the
+/// informational messages emitted while building the pool are downgraded to `debug!`, while
+/// configuration warnings and connection errors are still logged at their usual levels.
+///
+/// ## Example:
+/// ```rust,ignore
+/// let pool = build_pool_from_config_quiet(config).await?;
+/// ```
+#[tracing::instrument(name = "tidb_svc", err, skip(config))]
+pub async fn build_pool_from_config_quiet(config: TiDBConfig) -> Result<TidbPool, Error> {
+    build_pool(config, &PoolHooks::default(), true).await
+}
+
+/// Same as [`build_pool_from_config`], additionally invoking the callbacks in `hooks`.
+///
+/// ## Example:
+/// ```rust,ignore
+/// let hooks = PoolHooks {
+///     on_connection_initialized: Some(Arc::new(|elapsed| {
+///         info!("New TiDB connection initialized in {:?}", elapsed)
+///     })),
+/// };
+/// let pool = build_pool_with_hooks(config, hooks).await?;
+/// ```
+#[tracing::instrument(name = "tidb_svc", err, skip(config, hooks))]
+pub async fn build_pool_with_hooks(
+    config: TiDBConfig,
+    hooks: PoolHooks,
+) -> Result<TidbPool, Error> {
+    build_pool(config, &hooks, false).await
+}
+
+/// Logs at `debug` level when `quiet` is set and at `info` level otherwise.
+macro_rules! info_unless_quiet {
+    ($quiet:expr, $($arg:tt)+) => {
+        if $quiet {
+            debug!($($arg)+)
+        } else {
+            info!($($arg)+)
+        }
+    };
+}
+
+/// Builds a pool that opens no connection until one is needed, without awaiting.
+///
+/// Unlike [`build_pool_from_config`], this is not `async`, so it can be called from synchronous
+/// initialization code. The pool is always lazy, whatever the value of
+/// [`is_lazy`](PoolOptions::is_lazy); every other setting is applied the same way.
+///
+/// The pool spawns background maintenance tasks on the current Tokio runtime, so this must be
+/// called from within one, e.g. from a synchronous function called by an async one, or after
+/// [`Runtime::enter`](tokio::runtime::Runtime::enter). It panics otherwise.
+///
+/// ## Example:
+/// ```rust,ignore
+/// let runtime = tokio::runtime::Runtime::new()?;
+/// let pool = {
+///     let _guard = runtime.enter();
+///     build_pool_from_config_lazy(config)?
+/// };
+/// runtime.block_on(async { sqlx::query("SELECT 1").execute(&pool).await })?;
+/// ```
+pub fn build_pool_from_config_lazy(config: TiDBConfig) -> Result<TidbPool, Error> {
+    let (pool_options, conn_options, watchdog) =
+        prepare_pool(&config, &PoolHooks::default(), false)?;
+    let host = config.resolve_host_blocking().map_err(Error::Io)?;
+    let conn_options = with_resolved_host(&config, conn_options, &host);
+    log_pool_settings(&pool_options, false);
+    let pool = pool_options.connect_lazy_with(conn_options.clone());
+    info!("TiDB connection pool initialized successfully. Lazy mode: true");
+    if let Some(watchdog) = watchdog {
+        watchdog.spawn(conn_options);
+    }
+    Ok(pool)
+}
+
+async fn build_pool(config: TiDBConfig, hooks: &PoolHooks, quiet: bool) -> Result<TidbPool, Error> {
+    let (pool_options, conn_options, watchdog) = prepare_pool(&config, hooks, quiet)?;
+    let host = config.resolve_host().await.map_err(Error::Io)?;
+    let conn_options = with_resolved_host(&config, conn_options, &host);
+    let pool = connect_pool(&config, pool_options, conn_options.clone(), quiet).await?;
+    if let Some(watchdog) = watchdog {
+        watchdog.spawn(conn_options);
+    }
+    Ok(pool)
+}
+
+/// Points `conn_options` at `host`, as returned by [`TiDBConfig::resolve_host`].
+///
+/// Connections through a socket are left unchanged.
+fn with_resolved_host(
+    config: &TiDBConfig,
+    conn_options: MySqlConnectOptions,
+    host: &str,
+) -> MySqlConnectOptions {
+    if config.socket.is_some() {
+        conn_options
+    } else {
+        conn_options.host(host)
+    }
+}
+
+/// Validates `config` if requested, logs it and builds the pool and connection options, and
+/// the query watchdog to spawn once the pool is built, if one is configured.
+fn prepare_pool(
+    config: &TiDBConfig,
+    hooks: &PoolHooks,
+    quiet: bool,
+) -> Result<(MySqlPoolOptions, MySqlConnectOptions, Option<QueryWatchdog>), Error> {
+    // `validate` logs the config warnings itself
+    if config.validate_on_build {
+        config
+            .validate()
+            .map_err(|err| Error::Configuration(err.into()))?;
+    } else {
+        // Surface likely misconfigurations without failing the build
+        for warning in config.warnings() {
+            warn!("TiDB config: {}", warning);
+        }
+    }
+
+    info_unless_quiet!(quiet, "Initializing connection pool to TiDB...");
 
     // Log the database host for debugging purposes
-    info!("Database host: {}", config.get_host());
+    info_unless_quiet!(quiet, "Database host: {}", config.get_host());
+
+    let conn_options = build_connect_options(config)?;
+    let watchdog = QueryWatchdog::from_config(config);
+    let pool_options = build_pool_options(
+        config,
+        hooks,
+        watchdog.as_ref().map(QueryWatchdog::connection_ids),
+    );
+    Ok((pool_options, conn_options, watchdog))
+}
+
+/// Builds two pools from one configuration: one for OLTP and one for OLAP traffic.
+///
+/// Ad-hoc analytical queries are rarely repeated, so caching them only evicts the prepared
+/// statements of the hot transactional paths. The OLTP pool keeps the configured
+/// `statement_cache_capacity`, while the OLAP pool disables statement caching entirely.
+///
+/// The configuration is validated and logged once, and both pools use the same pool options,
+/// so together they may open up to twice `max_connections` connections. If
+/// `query_watchdog_secs` is set, a single watchdog covers the connections of both pools.
+///
+/// ## Example:
+/// ```rust,ignore
+/// let (oltp, olap) = build_oltp_olap_pools(config).await?;
+/// sqlx::query("UPDATE accounts SET balance = balance - ? WHERE id = ?").execute(&oltp).await?;
+/// sqlx::query(&report_sql).fetch_all(&olap).await?;
+/// ```
+pub async fn build_oltp_olap_pools(config: TiDBConfig) -> Result<(TidbPool, TidbPool), Error> {
+    let host = config.resolve_host().await.map_err(Error::Io)?;
+
+    let (pool_options, conn_options, watchdog) =
+        prepare_pool(&config, &PoolHooks::default(), false)?;
+    let conn_options = with_resolved_host(&config, conn_options, &host);
+
+    info!("Initializing OLTP connection pool to TiDB...");
+    let oltp = connect_pool(&config, pool_options.clone(), conn_options.clone(), false).await?;
+
+    info!("Initializing OLAP connection pool to TiDB (statement cache disabled)...");
+    let olap_conn_options = conn_options.clone().statement_cache_capacity(0);
+    let olap = connect_pool(&config, pool_options, olap_conn_options, false).await?;
+
+    // The cloned hooks record the connections of both pools in the same set
+    if let Some(watchdog) = watchdog {
+        watchdog.spawn(conn_options);
+    }
+    Ok((oltp, olap))
+}
+
+/// Builds one pool per named configuration, e.g. one per TiDB cluster, concurrently.
+///
+/// Each name is used as the [`pool_name`](PoolOptions::pool_name) of its pool unless the
+/// configuration sets one. Names must be unique: a repeated name fails with
+/// [`PoolBuildError::DuplicateName`] before any pool is built.
+///
+/// All pools are built at the same time, so eager pools (`is_lazy = false`) take as long to
+/// start as the slowest cluster rather than the sum of all clusters. Every build runs to
+/// completion; if any of them fails, the pools that were built are dropped and the failures
+/// are returned together, in input order, as [`PoolBuildError::Multiple`]. Invalid
+/// configurations are reported as [`PoolBuildError::Config`].
+///
+/// ## Example:
+/// ```rust,ignore
+/// let pools = build_pools_from_config([
+///     ("eu".to_string(), eu_config),
+///     ("us".to_string(), us_config),
+/// ])
+/// .await?;
+/// let eu = &pools["eu"];
+/// ```
+pub async fn build_pools_from_config(
+    configs: impl IntoIterator<Item = (String, TiDBConfig)>,
+) -> Result<HashMap<String, TidbPool>, PoolBuildError> {
+    let configs: Vec<(String, TiDBConfig)> = configs.into_iter().collect();
+    let mut names = HashSet::new();
+    if let Some((name, _)) = configs.iter().find(|(name, _)| !names.insert(name)) {
+        return Err(PoolBuildError::DuplicateName(name.clone()));
+    }
+
+    let builds = configs.into_iter().map(|(name, mut config)| async move {
+        config.pool_options.pool_name.get_or_insert_with(|| name.clone());
+        let result = build_pool_from_config(config).await;
+        (name, result)
+    });
 
-    // Define the port, defaulting to 4000 if not provided
-    let port = config.port.unwrap_or(4000_u16);
+    let mut pools = HashMap::new();
+    let mut errors = Vec::new();
+    for (name, result) in join_all(builds).await {
+        match result {
+            Ok(pool) => {
+                pools.insert(name, pool);
+            }
+            Err(err) => errors.push((name, PoolBuildError::from_connect_error(err))),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(pools)
+    } else {
+        Err(PoolBuildError::Multiple(errors))
+    }
+}
+
+/// Returns the connection options configured by `config`, without connecting.
+///
+/// The options carry the address or socket, credentials, SSL, charset and collation,
+/// statement cache and statement logging settings. Use them for standalone connections, e.g.
+/// `MySqlConnection::connect_with`, or for pools customized with
+/// [`pool_options_from_config`]. Session settings such as [`time_zone`](TiDBConfig::time_zone)
+/// and [`init_sql`](PoolOptions::init_sql) are applied by the pool options, not by these.
+///
+/// With an [`address_family`](TiDBConfig::address_family), the host is resolved with a blocking
+/// lookup; call [`TiDBConfig::resolve_host`] and set the host on the returned options instead
+/// from async code. Fails if the SSL or logging settings are invalid, or if the host cannot be
+/// resolved.
+///
+/// ## Example:
+/// ```rust,ignore
+/// let mut conn = MySqlConnection::connect_with(&connect_options_from_config(&config)?).await?;
+/// ```
+pub fn connect_options_from_config(config: &TiDBConfig) -> anyhow::Result<MySqlConnectOptions> {
+    let host = config.resolve_host_blocking()?;
+    Ok(with_resolved_host(config, build_connect_options(config)?, &host))
+}
+
+/// Builds the per-connection options (address, credentials, SSL, caching and logging).
+fn build_connect_options(config: &TiDBConfig) -> Result<MySqlConnectOptions, Error> {
+    // Connect through the Unix socket if one is configured, otherwise over TCP
+    let mut conn_options = MySqlConnectOptions::new();
+    if let Some(socket) = &config.socket {
+        if !config.host.is_empty() || config.port.is_some() {
+            warn!(
+                "TiDB socket `{}` is set; ignoring host `{}` and port {:?}",
+                socket, config.host, config.port
+            );
+        }
+        conn_options = conn_options.socket(socket);
+    } else if config.host.trim().is_empty() {
+        return Err(Error::Configuration(ConfigError::MissingField("host").into()));
+    } else {
+        // Define the port, defaulting to 4000 if not provided; the host is resolved according
+        // to the preferred address family, if any, by the callers
+        let port = config.port.unwrap_or(4000_u16);
+        conn_options = conn_options.host(config.host.as_str()).port(port);
+    }
 
     // Build the connection options
-    let mut conn_options = MySqlConnectOptions::new()
-        .host(config.host.as_str())
-        .port(port)
+    let mut conn_options = conn_options
         .database(config.database_name.as_str())
         .username(config.username.as_str())
         .password(config.password.as_str())
-        .statement_cache_capacity(if config.pool_options.statement_cache_capacity > 0 {
-            config.pool_options.statement_cache_capacity
-        } else {
-            1000
-        }); // Optimize by caching SQL statements
+        .statement_cache_capacity(config.pool_options.statement_cache_capacity); // Optimize by caching SQL statements
+
+    // Reject pool sizes above the policy cap, and SSL and authentication settings that cannot
+    // be honored
+    config
+        .check_pool_limits()
+        .and_then(|()| config.check_connect_settings())
+        .map_err(|err| Error::Configuration(err.into()))?;
 
-    // If SSL is enabled (ssl_ca is set), configure SSL options
-    if let Some(file_name) = config.ssl_ca {
-        conn_options = conn_options
-            .ssl_mode(MySqlSslMode::VerifyCa)
-            .ssl_ca(file_name);
+    // Configure SSL, verifying the server certificate when a CA is provided
+    conn_options = conn_options.ssl_mode(config.effective_ssl_mode());
+    if let Some(file_name) = &config.ssl_ca {
+        conn_options = conn_options.ssl_ca(file_name);
+    }
+    if let (Some(cert), Some(key)) = (&config.ssl_client_cert, &config.ssl_client_key) {
+        conn_options = conn_options.ssl_client_cert(cert).ssl_client_key(key);
+    }
+
+    // The clear-text plugin (e.g. LDAP simple authentication) is only used when asked for
+    if config.auth_plugin.as_deref() == Some("mysql_clear_password") {
+        conn_options = conn_options.enable_cleartext_plugin(true);
     }
 
-    // Configure logging options for SQL statements (for debugging)
+    // Use deterministic byte-wise comparisons when requested
+    if config.comparison_safe {
+        conn_options = conn_options.charset("utf8mb4").collation("utf8mb4_bin");
+    }
+    if let Some(charset) = &config.charset {
+        conn_options = conn_options.charset(charset);
+    }
+    if let Some(collation) = &config.collation {
+        conn_options = conn_options.collation(collation);
+    }
+
+    // Configure logging options for SQL statements (debug level and no slow query logging
+    // unless configured)
+    let (level, (slow_level, slow_threshold)) = config
+        .pool_options
+        .statements_log_level()
+        .and_then(|level| Ok((level, config.pool_options.slow_statements_log()?)))
+        .map_err(|err| Error::Configuration(err.into()))?;
     let conn_options = conn_options
-        .log_statements(tracing::log::LevelFilter::Debug) // Log SQL statements at debug level
-        .log_slow_statements(tracing::log::LevelFilter::Off, Duration::default()); // No slow query logging
+        .log_statements(level)
+        .log_slow_statements(slow_level, slow_threshold);
+
+    Ok(conn_options)
+}
 
+/// Returns the sqlx pool options configured by `config`, without connecting.
+///
+/// The options carry the pool sizes, timeouts and slow-acquire settings of
+/// [`PoolOptions`], and the callbacks implementing the session settings, `init_sql`, lifetime
+/// jitter, the use limit and lifecycle logging. Use it to tune the pool further before
+/// connecting, together with the options returned by [`connect_options_from_config`] for the
+/// same configuration.
+///
+/// Setting `after_connect`, `before_acquire` or `after_release` replaces the configured
+/// callback of the same name, and with it the settings that callback implements.
+///
+/// ## Example:
+/// ```rust,ignore
+/// let pool = pool_options_from_config(&config)
+///     .idle_timeout(Duration::from_secs(60))
+///     .connect_lazy_with(connect_options_from_config(&config)?);
+/// ```
+pub fn pool_options_from_config(config: &TiDBConfig) -> MySqlPoolOptions {
+    build_pool_options(config, &PoolHooks::default(), None)
+}
+
+/// Builds the pool options (sizes, timeouts, logging and connection hooks), recording the ids of
+/// new connections in `connection_ids` if given.
+fn build_pool_options(
+    config: &TiDBConfig,
+    hooks: &PoolHooks,
+    connection_ids: Option<&ConnectionIds>,
+) -> MySqlPoolOptions {
     // Build the pool options from the configuration, setting various timeouts and connection limits
-    let pool_options: MySqlPoolOptions = MySqlPoolOptions::new()
+    let mut pool_options: MySqlPoolOptions = MySqlPoolOptions::new()
         .max_connections(config.pool_options.max_connections) // Maximum number of connections
-        .min_connections(config.pool_options.min_connections) // Minimum number of connections
+        .min_connections(effective_min_connections(&config.pool_options)) // Minimum number of connections
         .idle_timeout(Duration::from_secs(config.pool_options.idle_timeout)) // Time to wait before closing idle connections
         .max_lifetime(Duration::from_secs(config.pool_options.max_lifetime)) // Maximum lifetime of a connection
-        .acquire_timeout(Duration::from_secs(config.pool_options.acquire_timeout)); // Timeout for acquiring a new connection
+        .acquire_timeout(Duration::from_secs(config.pool_options.acquire_timeout)) // Timeout for acquiring a new connection
+        .test_before_acquire(config.pool_options.test_before_acquire); // Ping idle connections before handing them out
+
+    // Configure slow-acquire logging
+    if let Some(threshold) = config.pool_options.acquire_slow_threshold_ms {
+        pool_options = pool_options.acquire_slow_threshold(Duration::from_millis(threshold));
+    }
+    pool_options = pool_options.acquire_slow_level(slow_acquire_level(&config.pool_options));
+
+    // Stagger connection retirement below `max_lifetime`, tracing acquires if requested
+    let max_lifetime = Duration::from_secs(config.pool_options.max_lifetime);
+    let jitter = config
+        .pool_options
+        .max_lifetime_jitter_secs
+        .filter(|&jitter| jitter > 0)
+        .map(Duration::from_secs);
+    // Per-pool seed, so that pools sharing connection ids still retire them at different ages
+    let seed = rand::random::<u64>();
+    let lifecycle = lifecycle_pool_name(config);
+    if jitter.is_some() || lifecycle.is_some() {
+        let lifecycle = lifecycle.clone();
+        pool_options = pool_options.before_acquire(move |conn, meta| {
+            let lifecycle = lifecycle.clone();
+            Box::pin(async move {
+                let keep = match jitter {
+                    Some(jitter) => {
+                        within_lifetime(conn, meta.age, max_lifetime, jitter, seed).await?
+                    }
+                    None => true,
+                };
+                if let Some(pool) = lifecycle {
+                    let event = if keep { "acquired" } else { "retired" };
+                    trace_lifecycle(&pool, conn, event).await;
+                }
+                Ok(keep)
+            })
+        });
+    }
+    if let Some(lifecycle) = lifecycle {
+        pool_options = pool_options.after_release(move |conn, _meta| {
+            let lifecycle = lifecycle.clone();
+            Box::pin(async move {
+                trace_lifecycle(&lifecycle, conn, "released").await;
+                Ok(true)
+            })
+        });
+    }
+
+    // Apply session settings and connection callbacks to every new connection
+    install_session_hook(pool_options, config, hooks, connection_ids)
+}
 
+/// Returns the level of sqlx's per-acquire slow-acquire event.
+///
+/// With sampling, the event is downgraded to `debug` rather than disabled, so that acquires
+/// bypassing [`TiDBHandle`](crate::TiDBHandle) are still logged.
+fn slow_acquire_level(options: &PoolOptions) -> tracing::log::LevelFilter {
+    if options.acquire_slow_log_sample.unwrap_or(1) > 1 {
+        tracing::log::LevelFilter::Debug
+    } else {
+        tracing::log::LevelFilter::Warn
+    }
+}
+
+/// Applies `on_invalid_pool_sizes` to `min_connections`.
+///
+/// The `Error` policy is enforced earlier, by [`TiDBConfig::check_pool_limits`].
+fn effective_min_connections(options: &PoolOptions) -> u32 {
+    let (min, max) = (options.min_connections, options.max_connections);
+    if min <= max {
+        return min;
+    }
+    match options.on_invalid_pool_sizes {
+        InvalidPoolSizes::Clamp => {
+            warn!(
+                "min_connections ({}) exceeds max_connections ({}); clamping min_connections to {}",
+                min, max, max
+            );
+            max
+        }
+        InvalidPoolSizes::Warn | InvalidPoolSizes::Error => {
+            warn!(
+                "min_connections ({}) exceeds max_connections ({}); the pool will not open more \
+                 than max_connections",
+                min, max
+            );
+            min
+        }
+    }
+}
+
+/// Returns `false` once `conn`, aged `age`, has outlived its jittered lifetime.
+///
+/// The connection id is only looked up once `age` enters the jitter window, so younger
+/// connections are kept without a round trip.
+async fn within_lifetime(
+    conn: &mut MySqlConnection,
+    age: Duration,
+    max_lifetime: Duration,
+    jitter: Duration,
+    seed: u64,
+) -> Result<bool, Error> {
+    if age < max_lifetime.saturating_sub(jitter) {
+        return Ok(true);
+    }
+    let id = connection_id(conn).await?;
+    Ok(age < jittered_lifetime(max_lifetime, jitter, seed, id))
+}
+
+/// Returns the lifetime of connection `connection_id`, spread uniformly over
+/// `[max_lifetime - jitter, max_lifetime]`.
+///
+/// The lifetime is derived from the connection id and `seed`, so every check on a connection
+/// compares its age against the same deadline.
+fn jittered_lifetime(
+    max_lifetime: Duration,
+    jitter: Duration,
+    seed: u64,
+    connection_id: u64,
+) -> Duration {
+    // SplitMix64 finalizer, spreading consecutive ids over the whole range
+    let mut hash = connection_id ^ seed;
+    hash = (hash ^ (hash >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    hash = (hash ^ (hash >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    hash ^= hash >> 31;
+    let fraction = (hash >> 11) as f64 / (1_u64 << 53) as f64;
+    max_lifetime - jitter.min(max_lifetime).mul_f64(fraction)
+}
+
+/// Creates the pool, connecting immediately unless the configuration asks for a lazy pool.
+async fn connect_pool(
+    config: &TiDBConfig,
+    pool_options: MySqlPoolOptions,
+    conn_options: MySqlConnectOptions,
+    quiet: bool,
+) -> Result<TidbPool, Error> {
     // Log the pool settings for debugging
-    log_pool_settings(&pool_options);
+    log_pool_settings(&pool_options, quiet);
 
     // Conditionally initialize the connection pool (lazy or immediate)
     let pool_db: MySqlPool = if config.pool_options.is_lazy {
         // Lazy connection pool: Connections are created only when needed
-        Ok(pool_options.connect_lazy_with(conn_options.clone()))
+        Ok(pool_options.connect_lazy_with(conn_options))
     } else {
-        // Immediate connection pool: Establish connections right away
-        pool_options.connect_with(conn_options.clone()).await
+        // Immediate connection pool: Establish `min_connections` connections right away
+        let connect = async {
+            let pool = pool_options.connect_with(conn_options).await?;
+            warmup_pool(&pool, pool.options().get_min_connections()).await?;
+            Ok(pool)
+        };
+        match config.pool_options.connect_timeout {
+            Some(secs) => tokio::time::timeout(Duration::from_secs(secs), connect)
+                .await
+                .unwrap_or(Err(Error::PoolTimedOut)),
+            None => connect.await,
+        }
     }
-    .map_err(|err| {
+    .inspect_err(|_| {
         // Handle connection errors and log the failure
-        error!(
-            "Failed to connect to TiDB server at {}:{}",
-            config.host, port
-        );
-        err
+        error!("Failed to connect to TiDB server at {}", config.get_host());
     })?;
 
     // Successfully initialized the pool
-    info!(
+    info_unless_quiet!(
+        quiet,
         "TiDB connection pool initialized successfully. Lazy mode: {}",
         config.pool_options.is_lazy
     );
     Ok(pool_db)
 }
 
+/// Acquires a connection from the pool only if one is immediately available.
+///
+/// Unlike [`MySqlPool::acquire`], this never waits: it returns `None` when the pool has no idle
+/// connection and is already at `max_connections`. It also never opens a new connection, so a
+/// freshly built lazy pool returns `None` until connections have been established.
+///
+/// Useful for non-critical background work that should be skipped rather than compete with
+/// request traffic for connections.
+///
+/// ## Example:
+/// ```rust,ignore
+/// if let Some(mut conn) = try_acquire(&pool) {
+///     sqlx::query("DELETE FROM sessions WHERE expired = 1").execute(&mut *conn).await?;
+/// }
+/// ```
+pub fn try_acquire(pool: &MySqlPool) -> Option<PoolConnection<MySql>> {
+    let conn = pool.try_acquire();
+    if conn.is_none() {
+        debug!("No idle TiDB connection immediately available");
+    }
+    conn
+}
+
+/// Opens connections until the pool holds at least `connections` of them, capped at its
+/// `max_connections`, and returns the number of connections held afterwards.
+///
+/// Warming runs the connection initialization of every newly opened connection, i.e. the
+/// session settings, [`init_command`](TiDBConfig::init_command),
+/// [`init_sql`](PoolOptions::init_sql) and `on_connection_initialized` hooks, so the first
+/// queries on warmed connections do not pay for it. The connections are acquired together, so
+/// existing idle connections count towards `connections`, and then returned to the pool.
+///
+/// Eager pools (`is_lazy = false`) are warmed up to `min_connections` while being built.
+///
+/// ## Example:
+/// ```rust,ignore
+/// let pool = build_pool_from_config(config).await?;
+/// warmup_pool(&pool, 16).await?;
+/// ```
+pub async fn warmup_pool(pool: &MySqlPool, connections: u32) -> Result<u32, Error> {
+    let connections = connections.min(pool.options().get_max_connections());
+    let held = try_join_all((0..connections).map(|_| pool.acquire())).await?;
+    debug!("Warmed {} TiDB connections", held.len());
+    drop(held);
+    Ok(pool.size())
+}
+
+/// Outcome of a [`refresh_idle`] pass.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RefreshReport {
+    /// Idle connections that were pinged.
+    pub probed: usize,
+    /// Connections that answered and were returned to the pool.
+    pub healthy: usize,
+    /// Connections that failed to answer and were closed.
+    pub replaced: usize,
+}
+
+/// Pings every idle connection of `pool` and closes those that do not answer.
+///
+/// Catches half-open connections, e.g. dropped by a load balancer or firewall while idle,
+/// before a query is sent on them. The idle connections are taken out of the pool together
+/// and pinged concurrently, each ping bounded by the pool's `acquire_timeout`. Healthy
+/// connections are returned to the pool; the others are closed, and the pool opens fresh
+/// connections in their place when needed, or right away to maintain `min_connections`.
+/// Connections checked out during the pass are not probed.
+///
+/// ## Example:
+/// ```rust,ignore
+/// let mut ticks = tokio::time::interval(Duration::from_secs(60));
+/// loop {
+///     ticks.tick().await;
+///     let report = refresh_idle(&pool).await?;
+///     if report.replaced > 0 {
+///         warn!("Replaced {} dead TiDB connections", report.replaced);
+///     }
+/// }
+/// ```
+pub async fn refresh_idle(pool: &MySqlPool) -> Result<RefreshReport, Error> {
+    let idle: Vec<PoolConnection<MySql>> =
+        std::iter::from_fn(|| pool.try_acquire()).take(pool.num_idle()).collect();
+    let timeout = pool.options().get_acquire_timeout();
+
+    let probes = idle.into_iter().map(|mut conn| async move {
+        let healthy = matches!(tokio::time::timeout(timeout, conn.ping()).await, Ok(Ok(())));
+        if !healthy {
+            conn.close_on_drop();
+        }
+        healthy
+    });
+    let results = join_all(probes).await;
+
+    let healthy = results.iter().filter(|&&healthy| healthy).count();
+    let report = RefreshReport {
+        probed: results.len(),
+        healthy,
+        replaced: results.len() - healthy,
+    };
+    if report.replaced > 0 {
+        warn!(
+            "Closed {} of {} idle TiDB connections that failed a ping",
+            report.replaced, report.probed
+        );
+    }
+    Ok(report)
+}
+
+/// Closes `pool`, waiting at most `drain` for checked-out connections to be returned.
+///
+/// New acquires fail as soon as this is called, idle connections are closed right away, and
+/// checked-out connections are closed when they are returned, so in-flight queries can finish.
+/// If some are still checked out when `drain` elapses, a warning with their number is logged
+/// and [`Error::PoolTimedOut`] is returned; the pool stays closed and the remaining connections
+/// are still closed when returned. Calling it again, or on a clone of a closed pool, is
+/// harmless.
+///
+/// ## Example:
+/// ```rust,ignore
+/// shutdown_signal.await;
+/// if let Err(err) = shutdown_pool(pool, Duration::from_secs(10)).await {
+///     error!("TiDB pool did not shut down cleanly: {}", err);
+/// }
+/// ```
+pub async fn shutdown_pool(pool: MySqlPool, drain: Duration) -> Result<(), Error> {
+    if tokio::time::timeout(drain, pool.close()).await.is_err() {
+        let active = pool.size().saturating_sub(pool.num_idle() as u32);
+        warn!(
+            "TiDB pool did not drain within {:?}; {} connections still active",
+            drain, active
+        );
+        return Err(Error::PoolTimedOut);
+    }
+    info!("TiDB connection pool closed");
+    Ok(())
+}
+
 /// Logs the settings of the connection pool for debugging purposes.
 ///
 /// This function logs the important settings of the `MySqlPoolOptions` such as
@@ -109,14 +720,754 @@ pub async fn build_pool_from_config(config: TiDBConfig) -> Result<TidbPool, Erro
 ///
 /// ## Parameters:
 /// - `pool_options`: The `MySqlPoolOptions` instance containing the pool settings.
-fn log_pool_settings(pool_options: &MySqlPoolOptions) {
-    info!("Connection pool settings:");
-    info!("  Max connections: {}", pool_options.get_max_connections());
-    info!("  Min connections: {}", pool_options.get_min_connections());
-    info!(
+/// - `quiet`: Log at `debug` instead of `info` level.
+fn log_pool_settings(pool_options: &MySqlPoolOptions, quiet: bool) {
+    info_unless_quiet!(quiet, "Connection pool settings:");
+    info_unless_quiet!(quiet, "  Max connections: {}", pool_options.get_max_connections());
+    info_unless_quiet!(quiet, "  Min connections: {}", pool_options.get_min_connections());
+    info_unless_quiet!(
+        quiet,
         "  Acquire timeout: {:?}",
         pool_options.get_acquire_timeout()
     );
-    info!("  Idle timeout: {:?}", pool_options.get_idle_timeout());
-    info!("  Max lifetime: {:?}", pool_options.get_max_lifetime());
+    info_unless_quiet!(quiet, "  Idle timeout: {:?}", pool_options.get_idle_timeout());
+    info_unless_quiet!(quiet, "  Max lifetime: {:?}", pool_options.get_max_lifetime());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{capture_logs, debug_field, integration_config};
+
+    /// Test that several lazy pools are built concurrently, each under its own name.
+    #[tokio::test]
+    async fn test_build_pools_from_config() {
+        let mut config = integration_config();
+        config.pool_options.is_lazy = true;
+        let configs = ["eu", "us", "ap"].map(|name| {
+            let mut config = config.clone();
+            config.database_name = format!("{}_db", name);
+            (name.to_string(), config)
+        });
+
+        let pools = build_pools_from_config(configs)
+            .await
+            .expect("Failed to build pools");
+
+        assert_eq!(pools.len(), 3);
+        for name in ["eu", "us", "ap"] {
+            let database = debug_field(&pools[name].connect_options(), "database");
+            assert_eq!(database, format!("Some(\"{}_db\")", name));
+        }
+    }
+
+    /// Test that the failing builds are reported together, by name and in input order.
+    #[tokio::test]
+    async fn test_build_pools_from_config_aggregates_errors() {
+        let mut config = integration_config();
+        config.pool_options.is_lazy = true;
+        config.validate_on_build = true;
+        let mut missing_user = config.clone();
+        missing_user.username = String::new();
+        let mut missing_database = config.clone();
+        missing_database.database_name = String::new();
+
+        let err = build_pools_from_config([
+            ("ok".to_string(), config),
+            ("missing_user".to_string(), missing_user),
+            ("missing_database".to_string(), missing_database),
+        ])
+        .await
+        .expect_err("Build should fail");
+
+        let PoolBuildError::Multiple(errors) = &err else {
+            panic!("unexpected error: {:?}", err);
+        };
+        let names: Vec<&str> = errors.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["missing_user", "missing_database"]);
+        assert!(matches!(
+            errors[0].1,
+            PoolBuildError::Config(ConfigError::MissingField("username"))
+        ));
+        assert!(err.to_string().starts_with("2 TiDB pools failed to build: `missing_user`: "));
+    }
+
+    /// Test that a name given to several configurations is rejected before building any pool.
+    #[tokio::test]
+    async fn test_build_pools_from_config_duplicate_name() {
+        let mut config = integration_config();
+        config.pool_options.is_lazy = true;
+
+        let err = build_pools_from_config([
+            ("eu".to_string(), config.clone()),
+            ("us".to_string(), config.clone()),
+            ("eu".to_string(), config),
+        ])
+        .await
+        .expect_err("Build should fail");
+
+        assert!(matches!(&err, PoolBuildError::DuplicateName(name) if name == "eu"), "{}", err);
+    }
+
+    /// Test that the OLTP pool keeps the configured statement cache and the OLAP pool disables it.
+    #[tokio::test]
+    async fn test_oltp_olap_pools_statement_cache_capacities() {
+        let mut config = integration_config();
+        config.pool_options.is_lazy = true;
+        config.pool_options.statement_cache_capacity = 250;
+
+        let (oltp, olap) = build_oltp_olap_pools(config)
+            .await
+            .expect("Failed to build lazy pools");
+
+        assert_eq!(
+            debug_field(&*oltp.connect_options(), "statement_cache_capacity"),
+            "250"
+        );
+        assert_eq!(
+            debug_field(&*olap.connect_options(), "statement_cache_capacity"),
+            "0"
+        );
+    }
+
+    /// Test that the OLTP and OLAP pools validate the configuration and log its warnings once.
+    #[tokio::test]
+    async fn test_oltp_olap_pools_prepare_once() {
+        let mut config = integration_config();
+        config.pool_options.is_lazy = true;
+        config.pool_options.min_connections = 8;
+        config.pool_options.max_connections = 4;
+        config.password = String::new();
+        let (guard, logs) = capture_logs();
+
+        let _pools = build_oltp_olap_pools(config.clone())
+            .await
+            .expect("Failed to build lazy pools");
+        drop(guard);
+        assert_eq!(logs.contents().matches("`password` is empty").count(), 1);
+        assert_eq!(logs.contents().matches("clamping min_connections").count(), 1);
+
+        config.ssl_ca = Some("/nonexistent/tidb_pool/ca.pem".into());
+        config.validate_on_build = true;
+        let err = build_oltp_olap_pools(config)
+            .await
+            .expect_err("Build should fail on a missing CA file");
+        assert!(matches!(err, Error::Configuration(_)));
+    }
+
+    /// Test that a quiet build emits no `info` events while a regular build does.
+    #[tokio::test]
+    async fn test_quiet_build_emits_no_info_events() {
+        let mut config = integration_config();
+        config.pool_options.is_lazy = true;
+
+        let (guard, logs) = capture_logs();
+        build_pool_from_config_quiet(config.clone())
+            .await
+            .expect("Failed to build lazy pool");
+        drop(guard);
+        assert!(!logs.contents().contains(" INFO "), "{}", logs.contents());
+        assert!(logs.contents().contains("Initializing connection pool"));
+
+        let (_guard, logs) = capture_logs();
+        build_pool_from_config(config)
+            .await
+            .expect("Failed to build lazy pool");
+        assert!(logs.contents().contains(" INFO "), "{}", logs.contents());
+    }
+
+    /// Test that jittered lifetimes stay within range, vary across connections and stay fixed
+    /// for a given connection.
+    #[test]
+    fn test_jittered_lifetime_varies_within_range() {
+        let max_lifetime = Duration::from_secs(1800);
+        let jitter = Duration::from_secs(300);
+
+        let lifetimes: Vec<Duration> =
+            (0..100).map(|id| jittered_lifetime(max_lifetime, jitter, 7, id)).collect();
+
+        assert!(lifetimes
+            .iter()
+            .all(|lifetime| (max_lifetime - jitter..=max_lifetime).contains(lifetime)));
+        let below_midpoint = lifetimes
+            .iter()
+            .filter(|&&lifetime| lifetime < max_lifetime - jitter / 2)
+            .count();
+        assert!((20..=80).contains(&below_midpoint), "{}", below_midpoint);
+
+        for (id, lifetime) in (0..100).zip(&lifetimes) {
+            assert_eq!(jittered_lifetime(max_lifetime, jitter, 7, id), *lifetime);
+        }
+        assert_ne!(jittered_lifetime(max_lifetime, jitter, 8, 0), lifetimes[0]);
+    }
+
+    /// Test that a jitter larger than `max_lifetime` is capped to it.
+    #[test]
+    fn test_jittered_lifetime_caps_jitter() {
+        let max_lifetime = Duration::from_secs(60);
+
+        let lifetime = jittered_lifetime(max_lifetime, Duration::from_secs(600), 7, 42);
+
+        assert!(lifetime <= max_lifetime);
+    }
+
+    /// Test that sampling downgrades sqlx's slow-acquire event instead of disabling it.
+    #[test]
+    fn test_slow_acquire_sampling_keeps_sqlx_logging() {
+        let mut options = PoolOptions {
+            acquire_slow_threshold_ms: Some(50),
+            ..PoolOptions::default()
+        };
+        assert_eq!(slow_acquire_level(&options), tracing::log::LevelFilter::Warn);
+
+        options.acquire_slow_log_sample = Some(1);
+        assert_eq!(slow_acquire_level(&options), tracing::log::LevelFilter::Warn);
+
+        options.acquire_slow_log_sample = Some(10);
+        assert_eq!(slow_acquire_level(&options), tracing::log::LevelFilter::Debug);
+    }
+
+    /// Test that `comparison_safe` selects the `utf8mb4` charset with the binary collation.
+    #[test]
+    fn test_comparison_safe_charset_and_collation() {
+        let mut config = integration_config();
+        let default_options = build_connect_options(&config).expect("Failed to build options");
+        assert_eq!(default_options.get_collation(), None);
+
+        config.comparison_safe = true;
+        let options = build_connect_options(&config).expect("Failed to build options");
+
+        assert_eq!(options.get_charset(), "utf8mb4");
+        assert_eq!(options.get_collation(), Some("utf8mb4_bin"));
+    }
+
+    /// Test that statement logging keeps its defaults unless levels are configured.
+    #[test]
+    fn test_statement_log_levels_applied() {
+        let mut config = integration_config();
+        let options = build_connect_options(&config).expect("Failed to build options");
+        assert_eq!(debug_field(&options, "statements_level"), "Debug");
+        assert_eq!(debug_field(&options, "slow_statements_level"), "Off");
+
+        config.pool_options.log_statements_level = Some("info".into());
+        config.pool_options.slow_statements_threshold_ms = Some(500);
+        let options = build_connect_options(&config).expect("Failed to build options");
+        assert_eq!(debug_field(&options, "statements_level"), "Info");
+        assert_eq!(debug_field(&options, "slow_statements_level"), "Warn");
+        assert_eq!(debug_field(&options, "slow_statements_duration"), "500ms");
+    }
+
+    /// Test that a lazy pool is built synchronously, and used inside the runtime afterwards.
+    #[test]
+    fn test_build_pool_from_config_lazy() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let mut config = integration_config();
+        config.pool_options.is_lazy = false;
+
+        let pool = {
+            let _guard = runtime.enter();
+            build_pool_from_config_lazy(config).expect("Failed to build pool")
+        };
+
+        assert_eq!(pool.size(), 0);
+        runtime.block_on(async {
+            assert!(try_acquire(&pool).is_none());
+            pool.close().await;
+        });
+    }
+
+    /// Test that a synchronously built pool runs queries.
+    #[test]
+    #[ignore = "requires a running TiDB instance"]
+    fn test_build_pool_from_config_lazy_runs_queries() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let pool = {
+            let _guard = runtime.enter();
+            build_pool_from_config_lazy(integration_config()).expect("Failed to build pool")
+        };
+
+        let one: i64 = runtime
+            .block_on(sqlx::query_scalar("SELECT 1").fetch_one(&pool))
+            .expect("Failed to run query");
+
+        assert_eq!(one, 1);
+    }
+
+    /// Test that a pool without idle connections has nothing to probe.
+    #[tokio::test]
+    async fn test_refresh_idle_without_connections() {
+        let mut config = integration_config();
+        config.pool_options.is_lazy = true;
+        let pool = build_pool_from_config(config)
+            .await
+            .expect("Failed to build pool");
+
+        let report = refresh_idle(&pool).await.expect("Failed to refresh");
+
+        assert_eq!(report, RefreshReport::default());
+    }
+
+    /// Test that every connection of a warmed pool is probed and found healthy.
+    #[tokio::test]
+    #[ignore = "requires a running TiDB instance"]
+    async fn test_refresh_idle_warmed_pool() {
+        let mut config = integration_config();
+        config.pool_options.min_connections = 0;
+        config.pool_options.max_connections = 3;
+        let pool = build_pool_from_config(config)
+            .await
+            .expect("Failed to build pool");
+        warmup_pool(&pool, 3).await.expect("Failed to warm pool");
+
+        let report = refresh_idle(&pool).await.expect("Failed to refresh");
+
+        assert_eq!(
+            report,
+            RefreshReport {
+                probed: 3,
+                healthy: 3,
+                replaced: 0
+            }
+        );
+        assert_eq!(pool.num_idle(), 3);
+    }
+
+    /// Test that an idle pool shuts down promptly, and that shutting down twice is harmless.
+    #[tokio::test]
+    async fn test_shutdown_idle_pool() {
+        let mut config = integration_config();
+        config.pool_options.is_lazy = true;
+        let pool = build_pool_from_config(config)
+            .await
+            .expect("Failed to build pool");
+
+        let started = std::time::Instant::now();
+        shutdown_pool(pool.clone(), Duration::from_secs(5))
+            .await
+            .expect("Failed to shut down");
+        shutdown_pool(pool.clone(), Duration::from_secs(5))
+            .await
+            .expect("Failed to shut down again");
+
+        assert!(started.elapsed() < Duration::from_secs(1));
+        assert!(pool.is_closed());
+    }
+
+    /// Test that a pool with a connection still checked out logs a warning with the number of
+    /// active connections and times out.
+    #[tokio::test]
+    #[ignore = "requires a running TiDB instance"]
+    async fn test_shutdown_drain_timeout_warns() {
+        let pool = build_pool_from_config(integration_config())
+            .await
+            .expect("Failed to build pool");
+        let conn = pool.acquire().await.expect("Failed to acquire");
+
+        let (_guard, logs) = capture_logs();
+        let err = shutdown_pool(pool.clone(), Duration::from_millis(200))
+            .await
+            .expect_err("Drain should time out");
+
+        assert!(matches!(err, Error::PoolTimedOut), "{}", err);
+        assert!(
+            logs.contents()
+                .contains("did not drain within 200ms; 1 connections still active"),
+            "{}",
+            logs.contents()
+        );
+        drop(conn);
+        assert!(pool.is_closed());
+    }
+
+    /// Test that the returned connect options carry the configured host, port and database.
+    #[test]
+    fn test_connect_options_from_config() {
+        let mut config = integration_config();
+        config.host = "tidb.internal".into();
+        config.port = Some(4001);
+        config.database_name = "orders".into();
+
+        let options = connect_options_from_config(&config).expect("Failed to build options");
+
+        assert_eq!(options.get_host(), "tidb.internal");
+        assert_eq!(options.get_port(), 4001);
+        assert_eq!(options.get_database(), Some("orders"));
+        assert_eq!(options.get_username(), config.username);
+    }
+
+    /// Test that the returned pool options carry the configured pool sizes and timeouts.
+    #[test]
+    fn test_pool_options_from_config() {
+        let mut config = integration_config();
+        config.pool_options.max_connections = 42;
+        config.pool_options.min_connections = 7;
+        config.pool_options.acquire_timeout = 12;
+
+        let pool_options = pool_options_from_config(&config);
+
+        assert_eq!(pool_options.get_max_connections(), 42);
+        assert_eq!(pool_options.get_min_connections(), 7);
+        assert_eq!(pool_options.get_acquire_timeout(), Duration::from_secs(12));
+        assert_eq!(pool_options.get_idle_timeout(), Some(Duration::from_secs(300)));
+    }
+
+    /// Test that `test_before_acquire` is forwarded to the sqlx pool options.
+    #[test]
+    fn test_test_before_acquire_forwarded() {
+        let mut config = integration_config();
+        let pool_options = build_pool_options(&config, &PoolHooks::default(), None);
+        assert_eq!(debug_field(&pool_options, "test_before_acquire"), "true");
+
+        config.pool_options.test_before_acquire = false;
+        let pool_options = build_pool_options(&config, &PoolHooks::default(), None);
+        assert_eq!(debug_field(&pool_options, "test_before_acquire"), "false");
+    }
+
+    /// Test that a configured socket replaces host and port, with a warning if they are set.
+    #[test]
+    fn test_socket_replaces_host_and_port() {
+        let mut config = integration_config();
+        config.socket = Some("/var/run/tidb/tidb.sock".into());
+        config.port = Some(4001);
+
+        let (_guard, logs) = capture_logs();
+        let options = build_connect_options(&config).expect("Failed to build options");
+
+        assert_eq!(
+            debug_field(&options, "socket"),
+            "Some(\"/var/run/tidb/tidb.sock\")"
+        );
+        assert!(logs.contents().contains("ignoring host"), "{}", logs.contents());
+
+        config.host = String::new();
+        config.port = None;
+        let (_guard, logs) = capture_logs();
+        build_connect_options(&config).expect("Failed to build options");
+        assert!(!logs.contents().contains("ignoring host"), "{}", logs.contents());
+    }
+
+    /// Test that the configured charset and collation reach the connect options.
+    #[test]
+    fn test_charset_and_collation_applied() {
+        let mut config = integration_config();
+        let options = build_connect_options(&config).expect("Failed to build options");
+        assert_eq!(options.get_charset(), "utf8mb4");
+        assert_eq!(options.get_collation(), None);
+
+        config.charset = Some("utf8mb4".into());
+        config.collation = Some("utf8mb4_unicode_ci".into());
+        let options = build_connect_options(&config).expect("Failed to build options");
+        assert_eq!(options.get_charset(), "utf8mb4");
+        assert_eq!(options.get_collation(), Some("utf8mb4_unicode_ci"));
+
+        config.charset = Some("latin1".into());
+        config.collation = None;
+        let options = build_connect_options(&config).expect("Failed to build options");
+        assert_eq!(options.get_charset(), "latin1");
+    }
+
+    /// Test that the clear-text plugin is only enabled when configured.
+    #[test]
+    fn test_cleartext_auth_plugin_enabled_on_request() {
+        let mut config = integration_config();
+        let options = build_connect_options(&config).expect("Failed to build options");
+        assert_eq!(debug_field(&options, "enable_cleartext_plugin"), "false");
+
+        config.auth_plugin = Some("mysql_clear_password".into());
+        let options = build_connect_options(&config).expect("Failed to build options");
+        assert_eq!(debug_field(&options, "enable_cleartext_plugin"), "true");
+    }
+
+    /// Test that the configured statement cache capacity reaches the connect options, including
+    /// zero to disable caching.
+    #[test]
+    fn test_statement_cache_capacity_honored() {
+        let mut config = integration_config();
+        for capacity in [37, 0] {
+            config.pool_options.statement_cache_capacity = capacity;
+
+            let options = build_connect_options(&config).expect("Failed to build options");
+
+            assert_eq!(
+                debug_field(&options, "statement_cache_capacity"),
+                capacity.to_string()
+            );
+        }
+    }
+
+    /// Test that the configured `ssl_mode` reaches the connect options.
+    #[test]
+    fn test_ssl_mode_applied_to_connect_options() {
+        let mut config = integration_config();
+        config.ssl_mode = Some(crate::config::SslMode::Required);
+
+        let options = build_connect_options(&config).expect("Failed to build options");
+
+        assert_eq!(debug_field(&options, "ssl_mode"), "Required");
+    }
+
+    /// Test that the client certificate and key reach the connect options.
+    #[test]
+    fn test_ssl_client_cert_and_key_applied() {
+        let mut config = integration_config();
+        config.ssl_client_cert = Some("/etc/ssl/client.pem".into());
+        config.ssl_client_key = Some("/etc/ssl/client.key".into());
+
+        let options = build_connect_options(&config).expect("Failed to build options");
+        let debug = format!("{:?}", options);
+
+        assert!(debug.contains("/etc/ssl/client.pem"), "{}", debug);
+        assert!(debug.contains("/etc/ssl/client.key"), "{}", debug);
+    }
+
+    /// Test that building fails when only the client certificate is configured.
+    #[tokio::test]
+    async fn test_build_rejects_client_cert_without_key() {
+        let mut config = integration_config();
+        config.pool_options.is_lazy = true;
+        config.ssl_client_cert = Some("/etc/ssl/client.pem".into());
+
+        let err = build_pool_from_config(config)
+            .await
+            .expect_err("Build should fail without a client key");
+
+        assert!(err.to_string().contains("sslClientKey"), "{}", err);
+    }
+
+    /// Test that building fails fast when SSL is required but no CA is configured.
+    #[tokio::test]
+    async fn test_build_rejects_required_ssl_without_ca() {
+        let mut config = integration_config();
+        config.pool_options.is_lazy = true;
+        config.require_ssl = true;
+
+        let err = build_pool_from_config(config)
+            .await
+            .expect_err("Build should fail without a CA");
+
+        assert!(matches!(err, Error::Configuration(_)));
+    }
+
+    /// Test that `connect_timeout` bounds an eager build even with a longer acquire timeout.
+    #[tokio::test]
+    async fn test_connect_timeout_applied() {
+        // A server that accepts connections but never sends the handshake.
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut config = integration_config();
+        config.host = "127.0.0.1".into();
+        config.port = Some(listener.local_addr().unwrap().port());
+        config.pool_options.is_lazy = false;
+        config.pool_options.min_connections = 0;
+        config.pool_options.acquire_timeout = 30;
+        config.pool_options.connect_timeout = Some(1);
+
+        let started = std::time::Instant::now();
+        let err = build_pool_from_config(config)
+            .await
+            .expect_err("Build should time out");
+
+        assert!(matches!(err, Error::PoolTimedOut), "{}", err);
+        assert!(started.elapsed() < Duration::from_secs(10));
+    }
+
+    /// Test that an eager build warms `min_connections` connections, each of which has already
+    /// run `init_sql`.
+    #[tokio::test]
+    #[ignore = "requires a running TiDB instance"]
+    async fn test_warmed_connections_ran_init_sql() {
+        let mut config = integration_config();
+        config.pool_options.is_lazy = false;
+        config.pool_options.min_connections = 3;
+        config.pool_options.max_connections = 5;
+        config.pool_options.init_sql = vec!["SET @tidb_pool_warmed = 1".into()];
+        let pool = build_pool_from_config(config)
+            .await
+            .expect("Failed to build pool");
+        assert!(pool.size() >= 3, "{}", pool.size());
+
+        let mut held = Vec::new();
+        while let Some(mut conn) = try_acquire(&pool) {
+            let warmed: Option<i64> = sqlx::query_scalar("SELECT @tidb_pool_warmed")
+                .fetch_one(&mut *conn)
+                .await
+                .expect("Failed to read variable");
+            assert_eq!(warmed, Some(1));
+            held.push(conn);
+        }
+        assert!(held.len() >= 3, "{}", held.len());
+        drop(held);
+
+        assert_eq!(warmup_pool(&pool, 10).await.expect("Failed to warm pool"), 5);
+    }
+
+    /// Test that config warnings are logged once when `validate_on_build` also validates.
+    #[tokio::test]
+    async fn test_validate_on_build_logs_warnings_once() {
+        let mut config = integration_config();
+        config.host = "127.0.0.1".into();
+        config.password = String::new();
+        config.pool_options.is_lazy = true;
+        config.validate_on_build = true;
+        let (_guard, logs) = capture_logs();
+
+        let _pool = build_pool_from_config(config).await.expect("Failed to build pool");
+
+        assert_eq!(logs.contents().matches("`password` is empty").count(), 1);
+    }
+
+    /// Test that a configuration with neither a host nor a socket is rejected.
+    #[tokio::test]
+    async fn test_empty_host_without_socket_rejected() {
+        let mut config = integration_config();
+        config.host = String::new();
+        config.pool_options.is_lazy = true;
+
+        let err = build_pool_from_config(config)
+            .await
+            .expect_err("Build should fail without a host");
+
+        assert!(matches!(err, Error::Configuration(_)));
+        assert!(err.to_string().contains("host"), "{}", err);
+    }
+
+    /// Test that `validate_on_build` runs the full validation before connecting.
+    #[tokio::test]
+    async fn test_validate_on_build() {
+        let mut config = integration_config();
+        config.pool_options.is_lazy = true;
+        config.ssl_ca = Some("/nonexistent/tidb_pool/ca.pem".into());
+        config.validate_on_build = true;
+
+        let err = build_pool_from_config(config)
+            .await
+            .expect_err("Build should fail on a missing CA file");
+
+        assert!(matches!(err, Error::Configuration(_)));
+        assert!(err.to_string().contains("sslCa"), "{}", err);
+    }
+
+    /// Test that the default `Clamp` policy lowers `min_connections` to `max_connections`.
+    #[test]
+    fn test_invalid_pool_sizes_clamp() {
+        let mut config = integration_config();
+        config.pool_options.min_connections = 8;
+        config.pool_options.max_connections = 4;
+
+        let pool_options = build_pool_options(&config, &PoolHooks::default(), None);
+
+        assert_eq!(pool_options.get_min_connections(), 4);
+    }
+
+    /// Test that clamping `min_connections` to `max_connections` logs both values.
+    #[test]
+    fn test_min_connections_clamped_with_warning() {
+        let mut config = integration_config();
+        config.pool_options.min_connections = 20;
+        config.pool_options.max_connections = 5;
+
+        let (_guard, logs) = capture_logs();
+        let pool_options = build_pool_options(&config, &PoolHooks::default(), None);
+
+        assert_eq!(pool_options.get_min_connections(), 5);
+        let logs = logs.contents();
+        assert!(logs.contains(" WARN "), "{}", logs);
+        assert!(logs.contains("min_connections (20) exceeds max_connections (5)"), "{}", logs);
+    }
+
+    /// Test that the `Warn` policy keeps `min_connections` and logs a warning.
+    #[test]
+    fn test_invalid_pool_sizes_warn() {
+        let mut config = integration_config();
+        config.pool_options.min_connections = 8;
+        config.pool_options.max_connections = 4;
+        config.pool_options.on_invalid_pool_sizes = InvalidPoolSizes::Warn;
+
+        let (_guard, logs) = capture_logs();
+        let pool_options = build_pool_options(&config, &PoolHooks::default(), None);
+
+        assert_eq!(pool_options.get_min_connections(), 8);
+        let logs = logs.contents();
+        assert!(logs.contains(" WARN "), "{}", logs);
+        assert!(logs.contains("min_connections (8) exceeds max_connections (4)"));
+    }
+
+    /// Test that the `Error` policy makes the build fail.
+    #[tokio::test]
+    async fn test_invalid_pool_sizes_error() {
+        let mut config = integration_config();
+        config.pool_options.is_lazy = true;
+        config.pool_options.min_connections = 8;
+        config.pool_options.max_connections = 4;
+        config.pool_options.on_invalid_pool_sizes = InvalidPoolSizes::Error;
+
+        let err = build_pool_from_config(config)
+            .await
+            .expect_err("Build should fail when min exceeds max");
+
+        assert!(matches!(err, Error::Configuration(_)));
+    }
+
+    /// Test that `verbose_lifecycle` traces connects and acquires with the connection id.
+    #[tokio::test]
+    #[ignore = "requires a running TiDB instance"]
+    async fn test_verbose_lifecycle_traces_events() {
+        let mut config = integration_config();
+        config.pool_options.verbose_lifecycle = true;
+        let (_guard, logs) = capture_logs();
+
+        let pool = build_pool_from_config(config).await.expect("Failed to build pool");
+        let conn = pool.acquire().await.expect("Failed to acquire");
+        drop(conn);
+
+        let logs = logs.contents();
+        assert!(logs.contains(" TRACE "), "{}", logs);
+        assert!(logs.contains("TiDB connection connected"), "{}", logs);
+        assert!(logs.contains("TiDB connection acquired"), "{}", logs);
+        assert!(logs.contains("connection_id="), "{}", logs);
+    }
+
+    /// Test that the `on_connection_initialized` hook fires with a non-zero duration on an
+    /// eager build.
+    #[tokio::test]
+    #[ignore = "requires a running TiDB instance"]
+    async fn test_on_connection_initialized_hook_reports_duration() {
+        use std::sync::{Arc, Mutex};
+
+        let durations = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&durations);
+        let hooks = PoolHooks {
+            on_connection_initialized: Some(Arc::new(move |elapsed| {
+                recorded.lock().unwrap().push(elapsed)
+            })),
+        };
+
+        let _pool = build_pool_with_hooks(integration_config(), hooks)
+            .await
+            .expect("Failed to build pool");
+
+        let durations = durations.lock().unwrap();
+        assert!(!durations.is_empty());
+        assert!(durations.iter().all(|elapsed| !elapsed.is_zero()));
+    }
+
+    /// Test that `try_acquire` returns `None` instead of waiting on a saturated pool.
+    #[tokio::test]
+    #[ignore = "requires a running TiDB instance"]
+    async fn test_try_acquire_saturated_pool() {
+        let mut config = integration_config();
+        config.pool_options.max_connections = 1;
+        config.pool_options.min_connections = 0;
+        let pool = build_pool_from_config(config).await.expect("Failed to build pool");
+
+        let held = pool.acquire().await.expect("Failed to acquire connection");
+        assert!(try_acquire(&pool).is_none());
+
+        drop(held);
+    }
 }
@@ -0,0 +1,290 @@
+//! Startup checks that build a pool and verify the server before returning it.
+
+use std::time::Duration;
+
+use sqlx::{Error, MySqlPool};
+use tokio::time::{sleep, timeout, Instant};
+
+use crate::{
+    build_pool_from_config,
+    config::TiDBConfig,
+    error::{unsupported_auth_plugin, PoolBuildError},
+    handle::TiDBHandle,
+    version::ServerVersion,
+};
+
+/// Delay between readiness probes while waiting for the server.
+const READY_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Builds the pool, waits until the server answers and checks its version.
+///
+/// Runs the following stages, failing with a stage-specific [`PoolBuildError`]:
+///
+/// 1. Validates the configuration ([`PoolBuildError::Config`]).
+/// 2. Builds the pool with [`build_pool_from_config`] ([`PoolBuildError::Connect`], or
+///    [`PoolBuildError::UnsupportedAuthPlugin`] if the server requests an authentication
+///    plugin the driver cannot negotiate).
+/// 3. Runs `SELECT 1` until it succeeds or `timeout` elapses ([`PoolBuildError::NotReady`]).
+/// 4. If [`min_server_version`](TiDBConfig::min_server_version) is set, checks `VERSION()`
+///    against it ([`PoolBuildError::ServerVersion`]).
+///
+/// ## Example:
+/// ```rust,ignore
+/// let handle = preflight(config, Duration::from_secs(30)).await?;
+/// let pool = handle.pool();
+/// ```
+pub async fn preflight(config: TiDBConfig, timeout: Duration) -> Result<TiDBHandle, PoolBuildError> {
+    preflight_with(&LiveBackend, config, timeout).await
+}
+
+/// Operations performed against the server during [`preflight`].
+pub(crate) trait PreflightBackend {
+    async fn build(&self, config: &TiDBConfig) -> Result<MySqlPool, Error>;
+    async fn ping(&self, pool: &MySqlPool) -> Result<(), Error>;
+    async fn server_version(&self, pool: &MySqlPool) -> Result<String, Error>;
+}
+
+/// Backend talking to a real server.
+struct LiveBackend;
+
+impl PreflightBackend for LiveBackend {
+    async fn build(&self, config: &TiDBConfig) -> Result<MySqlPool, Error> {
+        build_pool_from_config(config.clone()).await
+    }
+
+    async fn ping(&self, pool: &MySqlPool) -> Result<(), Error> {
+        sqlx::query("SELECT 1").execute(pool).await.map(|_| ())
+    }
+
+    async fn server_version(&self, pool: &MySqlPool) -> Result<String, Error> {
+        sqlx::query_scalar("SELECT VERSION()").fetch_one(pool).await
+    }
+}
+
+pub(crate) async fn preflight_with<B: PreflightBackend>(
+    backend: &B,
+    config: TiDBConfig,
+    ready_timeout: Duration,
+) -> Result<TiDBHandle, PoolBuildError> {
+    config.validate()?;
+
+    let pool = backend
+        .build(&config)
+        .await
+        .map_err(PoolBuildError::from_connect_error)?;
+
+    wait_until_ready(backend, &pool, ready_timeout).await?;
+
+    if let Some(required) = &config.min_server_version {
+        check_server_version(backend, &pool, required).await?;
+    }
+
+    info!("TiDB preflight checks passed");
+    Ok(TiDBHandle::new(pool, config))
+}
+
+async fn wait_until_ready<B: PreflightBackend>(
+    backend: &B,
+    pool: &MySqlPool,
+    ready_timeout: Duration,
+) -> Result<(), PoolBuildError> {
+    let deadline = Instant::now() + ready_timeout;
+    let mut last_error = None;
+
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(PoolBuildError::NotReady {
+                timeout: ready_timeout,
+                last_error,
+            });
+        }
+
+        match timeout(remaining, backend.ping(pool)).await {
+            Ok(Ok(())) => return Ok(()),
+            Ok(Err(err)) => {
+                // Retrying cannot succeed if the server demands an unsupported plugin
+                if let Some(plugin) = unsupported_auth_plugin(&err) {
+                    return Err(PoolBuildError::UnsupportedAuthPlugin(plugin.to_owned()));
+                }
+                debug!("TiDB not ready yet: {}", err);
+                last_error = Some(err);
+            }
+            Err(_) => continue,
+        }
+
+        sleep(READY_POLL_INTERVAL.min(deadline.saturating_duration_since(Instant::now()))).await;
+    }
+}
+
+async fn check_server_version<B: PreflightBackend>(
+    backend: &B,
+    pool: &MySqlPool,
+    required: &str,
+) -> Result<(), PoolBuildError> {
+    let found = backend
+        .server_version(pool)
+        .await
+        .map_err(PoolBuildError::Connect)?;
+
+    let satisfied = ServerVersion::parse(&found).is_some_and(|found| found.at_least(required));
+    if satisfied {
+        Ok(())
+    } else {
+        Err(PoolBuildError::ServerVersion {
+            required: required.to_string(),
+            found,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use sqlx::mysql::{MySqlConnectOptions, MySqlPoolOptions};
+
+    use super::*;
+    use crate::{error::ConfigError, test_support::integration_config};
+
+    /// Fake backend whose behavior is scripted per test.
+    struct FakeBackend {
+        fail_build: bool,
+        failing_pings: u32,
+        version: &'static str,
+        pings: AtomicU32,
+    }
+
+    impl FakeBackend {
+        fn healthy() -> Self {
+            FakeBackend {
+                fail_build: false,
+                failing_pings: 0,
+                version: "5.7.25-TiDB-v7.5.0",
+                pings: AtomicU32::new(0),
+            }
+        }
+    }
+
+    impl PreflightBackend for FakeBackend {
+        async fn build(&self, _config: &TiDBConfig) -> Result<MySqlPool, Error> {
+            if self.fail_build {
+                return Err(Error::PoolTimedOut);
+            }
+            Ok(MySqlPoolOptions::new()
+                .connect_lazy_with(MySqlConnectOptions::new().host("127.0.0.1").port(1)))
+        }
+
+        async fn ping(&self, _pool: &MySqlPool) -> Result<(), Error> {
+            if self.pings.fetch_add(1, Ordering::Relaxed) < self.failing_pings {
+                Err(Error::PoolTimedOut)
+            } else {
+                Ok(())
+            }
+        }
+
+        async fn server_version(&self, _pool: &MySqlPool) -> Result<String, Error> {
+            Ok(self.version.to_string())
+        }
+    }
+
+    fn fake_config(min_server_version: Option<&str>) -> TiDBConfig {
+        let mut config = integration_config();
+        config.min_server_version = min_server_version.map(String::from);
+        config
+    }
+
+    /// Test the happy path, including a ping that only succeeds on the second try.
+    #[tokio::test]
+    async fn test_preflight_succeeds_after_retry() {
+        let backend = FakeBackend {
+            failing_pings: 1,
+            ..FakeBackend::healthy()
+        };
+
+        let handle = preflight_with(&backend, fake_config(Some("7.1.0")), Duration::from_secs(5))
+            .await
+            .expect("Preflight should succeed");
+
+        assert_eq!(handle.config().min_server_version.as_deref(), Some("7.1.0"));
+        assert_eq!(backend.pings.load(Ordering::Relaxed), 2);
+    }
+
+    /// Test that an invalid configuration fails before anything is built.
+    #[tokio::test]
+    async fn test_preflight_invalid_config() {
+        let mut config = fake_config(None);
+        config.host = String::new();
+
+        let err = preflight_with(&FakeBackend::healthy(), config, Duration::from_secs(1))
+            .await
+            .expect_err("Preflight should fail");
+
+        assert!(matches!(err, PoolBuildError::Config(ConfigError::MissingField("host"))));
+    }
+
+    /// Test that a build failure is reported as a connect error.
+    #[tokio::test]
+    async fn test_preflight_build_failure() {
+        let backend = FakeBackend {
+            fail_build: true,
+            ..FakeBackend::healthy()
+        };
+
+        let err = preflight_with(&backend, fake_config(None), Duration::from_secs(1))
+            .await
+            .expect_err("Preflight should fail");
+
+        assert!(matches!(err, PoolBuildError::Connect(Error::PoolTimedOut)));
+    }
+
+    /// Test that a server that never answers is reported as not ready.
+    #[tokio::test]
+    async fn test_preflight_not_ready() {
+        let backend = FakeBackend {
+            failing_pings: u32::MAX,
+            ..FakeBackend::healthy()
+        };
+
+        let err = preflight_with(&backend, fake_config(None), Duration::from_millis(600))
+            .await
+            .expect_err("Preflight should fail");
+
+        assert!(matches!(
+            err,
+            PoolBuildError::NotReady {
+                last_error: Some(Error::PoolTimedOut),
+                ..
+            }
+        ));
+    }
+
+    /// Test that an old server version is rejected.
+    #[tokio::test]
+    async fn test_preflight_server_too_old() {
+        let backend = FakeBackend {
+            version: "5.7.25-TiDB-v6.5.3",
+            ..FakeBackend::healthy()
+        };
+
+        let err = preflight_with(&backend, fake_config(Some("7.1.0")), Duration::from_secs(1))
+            .await
+            .expect_err("Preflight should fail");
+
+        assert!(matches!(err, PoolBuildError::ServerVersion { .. }));
+    }
+
+    /// Test preflight against a real server.
+    #[tokio::test]
+    #[ignore = "requires a running TiDB instance"]
+    async fn test_preflight_live() {
+        let handle = preflight(integration_config(), Duration::from_secs(10))
+            .await
+            .expect("Preflight should succeed");
+
+        sqlx::query("SELECT 1")
+            .execute(handle.pool())
+            .await
+            .expect("Pool should be usable");
+    }
+}
@@ -0,0 +1,871 @@
+//! Query helpers built on top of a [`MySqlPool`].
+
+use std::{io, time::Duration};
+
+use futures_util::{Stream, TryStreamExt};
+use sqlx::{
+    mysql::{MySqlArguments, MySqlQueryResult, MySqlRow},
+    Error, Executor, FromRow, MySqlConnection, MySqlPool, Row,
+};
+
+use crate::{
+    count::Count,
+    error::{MissingWhereClause, StatementWarnings},
+    tables_family::{quote_identifier, validate_identifier, TableName},
+};
+
+/// How the multi-row helpers collect result sets.
+///
+/// The default mode for a pool is configured through
+/// [`PoolOptions::default_fetch_streaming`](crate::PoolOptions::default_fetch_streaming) and
+/// retrieved with [`PoolOptions::fetch_mode`](crate::PoolOptions::fetch_mode).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FetchMode {
+    /// Load the whole result set into memory with `fetch_all`.
+    Buffered,
+    /// Stream rows from the server, keeping at most `max_rows` of them.
+    Streaming {
+        /// Maximum number of rows to accumulate before stopping.
+        max_rows: usize,
+    },
+}
+
+/// Runs `query` and decodes every returned row into `T`.
+///
+/// In [`FetchMode::Buffered`] mode all rows are returned. In [`FetchMode::Streaming`] mode rows
+/// are consumed one at a time and accumulation stops once `max_rows` rows were collected; the
+/// remaining rows are discarded and a warning is logged.
+///
+/// The number of rows returned is recorded as the `rows` field of a `DEBUG` event.
+///
+/// ## Example:
+/// ```rust,ignore
+/// #[derive(sqlx::FromRow)]
+/// struct User { id: u64, name: String }
+///
+/// let mode = config.pool_options.fetch_mode();
+/// let users: Vec<User> = fetch_rows(&pool, "SELECT id, name FROM users", mode).await?;
+/// ```
+pub async fn fetch_rows<T>(pool: &MySqlPool, query: &str, mode: FetchMode) -> Result<Vec<T>, Error>
+where
+    T: for<'r> FromRow<'r, MySqlRow> + Send + Unpin,
+{
+    let query = sqlx::query_as::<_, T>(query);
+    let rows = match mode {
+        FetchMode::Buffered => query.fetch_all(pool).await?,
+        FetchMode::Streaming { max_rows } => collect_capped(query.fetch(pool), max_rows).await?,
+    };
+    debug!(rows = rows.len(), "Fetched rows from TiDB");
+    Ok(rows)
+}
+
+/// Runs `query` with `args` bound to its `?` placeholders and decodes the first row into `T`.
+///
+/// Returns `Ok(None)` when the query returns no rows, so "not found" is not an error. Rows after
+/// the first are ignored; add a `LIMIT 1` to avoid transferring them.
+///
+/// ## Example:
+/// ```rust,ignore
+/// use sqlx::Arguments;
+///
+/// let mut args = MySqlArguments::default();
+/// args.add(user_id)?;
+/// let query = "SELECT id, name FROM users WHERE id = ?";
+/// let user: Option<User> = find_one(&pool, query, args).await?;
+/// ```
+pub async fn find_one<T>(
+    pool: &MySqlPool,
+    query: &str,
+    args: MySqlArguments,
+) -> Result<Option<T>, Error>
+where
+    T: for<'r> FromRow<'r, MySqlRow> + Send + Unpin,
+{
+    sqlx::query_as_with::<_, T, _>(query, args)
+        .fetch_optional(pool)
+        .await
+}
+
+/// Counts the rows of `table` with `SELECT COUNT(*)`.
+pub async fn count_rows(pool: &MySqlPool, table: &TableName) -> Result<Count, Error> {
+    let query = format!("SELECT COUNT(*) FROM {}", table.quoted());
+    sqlx::query_as(&query).fetch_one(pool).await
+}
+
+/// Asserts that `table` holds exactly `expected` rows, for use in tests.
+///
+/// Only available in this crate's tests and with the `testing` feature.
+///
+/// # Panics
+///
+/// Panics with the table name and both counts if they differ, or if the rows cannot be counted.
+///
+/// ## Example:
+/// ```rust,ignore
+/// let orders = TableName::new("orders")?;
+/// assert_count(&pool, &orders, 3).await;
+/// ```
+#[cfg(any(test, feature = "testing"))]
+pub async fn assert_count(pool: &MySqlPool, table: &TableName, expected: i64) {
+    let actual = count_rows(pool, table)
+        .await
+        .unwrap_or_else(|err| panic!("failed to count the rows of `{}`: {}", table, err));
+    assert_eq!(
+        *actual, expected,
+        "expected `{}` to hold {} rows, found {}",
+        table, expected, *actual
+    );
+}
+
+/// Returns `true` if `table` exists.
+///
+/// Looks the table up in `information_schema.tables`, in its own database for a qualified
+/// `db.table` name and in the connection's current database otherwise.
+///
+/// ## Example:
+/// ```rust,ignore
+/// let users = TableName::new("users")?;
+/// if !table_exists(&pool, &users).await? {
+///     run_migrations(&pool).await?;
+/// }
+/// ```
+pub async fn table_exists(pool: &MySqlPool, table: &TableName) -> Result<bool, Error> {
+    let exists: i64 = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM information_schema.tables \
+         WHERE table_schema = COALESCE(?, DATABASE()) AND table_name = ?)",
+    )
+    .bind(table.database())
+    .bind(table.table())
+    .fetch_one(pool)
+    .await?;
+    Ok(exists != 0)
+}
+
+/// Counts the distinct non-`NULL` values of `column` in `table`.
+///
+/// Runs `SELECT COUNT(DISTINCT <column>) FROM <table>`. The column must be a plain identifier;
+/// anything else is rejected with [`Error::InvalidArgument`] before the query is sent.
+///
+/// ## Example:
+/// ```rust,ignore
+/// let users = TableName::new("users")?;
+/// let countries = count_distinct(&pool, &users, "country").await?;
+/// ```
+pub async fn count_distinct(
+    pool: &MySqlPool,
+    table: &TableName,
+    column: &str,
+) -> Result<Count, Error> {
+    validate_identifier(column)?;
+    let query = format!(
+        "SELECT COUNT(DISTINCT {}) FROM {}",
+        quote_identifier(column),
+        table.quoted()
+    );
+    sqlx::query_as(&query).fetch_one(pool).await
+}
+
+/// Runs a `SUM` query and returns the result as a [`Count`], mapping a `NULL` sum to zero.
+///
+/// `SUM` over zero rows returns `NULL` rather than `0`, and over integer columns it returns a
+/// `DECIMAL`; neither decodes into `Count` directly. `query` must return a single row with a
+/// single column; it is wrapped as `SELECT CAST(COALESCE((<query>), 0) AS SIGNED)` so that an
+/// empty input yields `Count(0)`. Sums that do not fit in an `i64` fail to decode.
+///
+/// ## Example:
+/// ```rust,ignore
+/// let total = fetch_sum(&pool, "SELECT SUM(quantity) FROM orders WHERE status = 'open'").await?;
+/// ```
+pub async fn fetch_sum(pool: &MySqlPool, query: &str) -> Result<Count, Error> {
+    let query = format!("SELECT CAST(COALESCE(({}), 0) AS SIGNED)", query);
+    sqlx::query_as(&query).fetch_one(pool).await
+}
+
+/// Deletes the rows of `table` matching `where_clause` and returns how many were removed.
+///
+/// Runs `DELETE FROM <table> WHERE <where_clause>` with `args` bound to its `?` placeholders.
+/// A full-table delete is never issued: an empty or blank `where_clause` is rejected with a
+/// [`MissingWhereClause`] error before anything is sent to the server. Values should be passed
+/// through `args` rather than formatted into `where_clause`.
+///
+/// Server warnings never fail the delete here, as a bare pool carries no configuration:
+/// [`treat_warnings_as_errors`](crate::PoolOptions::treat_warnings_as_errors) is only applied by
+/// [`TiDBHandle::delete_where`](crate::TiDBHandle::delete_where).
+///
+/// ## Example:
+/// ```rust,ignore
+/// use sqlx::Arguments;
+///
+/// let sessions = TableName::new("sessions")?;
+/// let mut args = MySqlArguments::default();
+/// args.add(cutoff)?;
+/// let deleted = delete_where(&pool, &sessions, "expires_at < ?", args).await?;
+/// ```
+pub async fn delete_where(
+    pool: &MySqlPool,
+    table: &TableName,
+    where_clause: &str,
+    args: MySqlArguments,
+) -> Result<Count, Error> {
+    delete_rows(pool, table, where_clause, args, false).await
+}
+
+/// Implements [`delete_where`], failing on server warnings if `treat_warnings_as_errors` is set.
+pub(crate) async fn delete_rows(
+    pool: &MySqlPool,
+    table: &TableName,
+    where_clause: &str,
+    args: MySqlArguments,
+    treat_warnings_as_errors: bool,
+) -> Result<Count, Error> {
+    let where_clause = where_clause.trim();
+    if where_clause.is_empty() {
+        return Err(MissingWhereClause::new(&table.to_string()).into());
+    }
+
+    let query = format!("DELETE FROM {} WHERE {}", table.quoted(), where_clause);
+    let result = execute_mutation(pool, &query, args, treat_warnings_as_errors).await?;
+    Ok(Count(i64::try_from(result.rows_affected()).unwrap_or(i64::MAX)))
+}
+
+/// Executes a mutating statement.
+///
+/// If `treat_warnings_as_errors` is set, the statement runs in a transaction followed by
+/// `SHOW WARNINGS`, and the transaction is rolled back with a [`StatementWarnings`] error if
+/// the server reported any warning.
+async fn execute_mutation(
+    pool: &MySqlPool,
+    query: &str,
+    args: MySqlArguments,
+    treat_warnings_as_errors: bool,
+) -> Result<MySqlQueryResult, Error> {
+    if !treat_warnings_as_errors {
+        return sqlx::query_with(query, args).execute(pool).await;
+    }
+
+    let mut tx = pool.begin().await?;
+    let result = sqlx::query_with(query, args).execute(&mut *tx).await?;
+    let warnings = fetch_warnings(&mut tx).await?;
+    if !warnings.is_empty() {
+        tx.rollback().await?;
+        return Err(StatementWarnings::new(warnings).into());
+    }
+    tx.commit().await?;
+    Ok(result)
+}
+
+/// Runs `query` and returns the number of affected rows with the warnings it produced.
+///
+/// Warnings belong to the session that ran the statement, so `query` and `SHOW WARNINGS` run
+/// on the same connection, acquired from `pool` for the duration of the call. Warnings are
+/// formatted as `<Level> <Code>: <Message>`, e.g.
+/// `Warning 1265: Data truncated for column 'qty' at row 1`, and `Note` rows are left out. The
+/// server keeps at most `max_error_count` (64 by default) of them. The statement is not rolled
+/// back when it produces warnings.
+///
+/// ## Example:
+/// ```rust,ignore
+/// let (inserted, warnings) =
+///     execute_with_warnings(&pool, "INSERT INTO stock SELECT * FROM staging_stock").await?;
+/// for warning in &warnings {
+///     warn!("Stock import: {}", warning);
+/// }
+/// ```
+pub async fn execute_with_warnings(
+    pool: &MySqlPool,
+    query: &str,
+) -> Result<(Count, Vec<String>), Error> {
+    let mut conn = pool.acquire().await?;
+    let result = conn.execute(query).await?;
+    let warnings = fetch_warnings(&mut conn).await?;
+    let count = Count(i64::try_from(result.rows_affected()).unwrap_or(i64::MAX));
+    Ok((count, warnings))
+}
+
+/// Returns the warnings and errors of the last statement run on `conn`, formatted as
+/// `<Level> <Code>: <Message>`.
+///
+/// Rows at `Note` level, such as the one reported by `DROP TABLE IF EXISTS` for a missing
+/// table, are left out.
+pub(crate) async fn fetch_warnings(conn: &mut MySqlConnection) -> Result<Vec<String>, Error> {
+    // The text protocol keeps the prepare of a statement from resetting the warnings
+    let rows = sqlx::raw_sql("SHOW WARNINGS").fetch_all(&mut *conn).await?;
+    let mut warnings = Vec::new();
+    for row in &rows {
+        let level: String = row.try_get_unchecked(0)?;
+        let code: u64 = row.try_get_unchecked(1)?;
+        let message: String = row.try_get_unchecked(2)?;
+        warnings.extend(format_warning(&level, code, &message));
+    }
+    Ok(warnings)
+}
+
+/// Formats a `SHOW WARNINGS` row, or returns `None` if its level is neither `Warning` nor
+/// `Error`.
+fn format_warning(level: &str, code: u64, message: &str) -> Option<String> {
+    matches!(level, "Warning" | "Error").then(|| format!("{} {}: {}", level, code, message))
+}
+
+/// Returns the server's current UTC time, with microsecond precision.
+///
+/// Runs `SELECT UTC_TIMESTAMP(6)`. Comparing the result with the local clock gives an estimate
+/// of the clock skew between the application and the database, accurate to within the query's
+/// round-trip time.
+///
+/// ## Example:
+/// ```rust,ignore
+/// let skew = chrono::Utc::now() - server_now(&pool).await?;
+/// if skew.num_milliseconds().abs() > 500 {
+///     warn!("Clock skew with TiDB: {}", skew);
+/// }
+/// ```
+#[cfg(feature = "chrono")]
+pub async fn server_now(pool: &MySqlPool) -> Result<chrono::DateTime<chrono::Utc>, Error> {
+    let now: chrono::NaiveDateTime = sqlx::query_scalar("SELECT UTC_TIMESTAMP(6)")
+        .fetch_one(pool)
+        .await?;
+    Ok(now.and_utc())
+}
+
+/// Renames table `from` to `to` with `RENAME TABLE`.
+///
+/// Both names are validated identifiers, so nothing but the two table names reaches the
+/// statement. Qualified names (`db.table`) move the table to another database. Renaming a
+/// table to itself is rejected with [`Error::InvalidArgument`] before anything is sent to the
+/// server, and the server rejects the rename if `to` already exists.
+///
+/// The rename is atomic: concurrent statements see the table under either its old or its new
+/// name, never neither, and statements already running on it complete first. It is a DDL
+/// statement, so it implicitly commits any open transaction on the connection and cannot be
+/// rolled back. A blue-green swap made of several renames is not atomic as a whole: between
+/// two calls, the old name briefly does not exist.
+///
+/// ## Example:
+/// ```rust,ignore
+/// let live = TableName::new("prices")?;
+/// let staged = TableName::new("prices_next")?;
+/// rename_table(&pool, &live, &TableName::new("prices_old")?).await?;
+/// rename_table(&pool, &staged, &live).await?;
+/// ```
+#[cfg(feature = "unsafe_admin")]
+pub async fn rename_table(pool: &MySqlPool, from: &TableName, to: &TableName) -> Result<(), Error> {
+    if from == to {
+        return Err(Error::InvalidArgument(format!(
+            "cannot rename table {} to itself",
+            from
+        )));
+    }
+
+    let query = format!("RENAME TABLE {} TO {}", from.quoted(), to.quoted());
+    sqlx::raw_sql(&query).execute(pool).await?;
+    info!("Renamed table {} to {}", from, to);
+    Ok(())
+}
+
+/// Checks that the pool can hand out a working connection, e.g. for a readiness probe.
+///
+/// Acquires a connection and runs `SELECT 1` on it. Acquiring may open a new connection and is
+/// bounded by the pool's `acquire_timeout`; see [`health_check_with_timeout`] for a tighter
+/// limit.
+///
+/// ## Example:
+/// ```rust,ignore
+/// async fn ready(pool: &MySqlPool) -> StatusCode {
+///     match health_check(pool).await {
+///         Ok(()) => StatusCode::OK,
+///         Err(_) => StatusCode::SERVICE_UNAVAILABLE,
+///     }
+/// }
+/// ```
+pub async fn health_check(pool: &MySqlPool) -> Result<(), Error> {
+    let mut conn = pool.acquire().await?;
+    conn.execute("SELECT 1").await?;
+    Ok(())
+}
+
+/// Runs [`health_check`], failing if it does not complete within `timeout`.
+///
+/// An elapsed timeout is reported as an [`Error::Io`] of kind
+/// [`TimedOut`](std::io::ErrorKind::TimedOut) whose message names the timeout.
+pub async fn health_check_with_timeout(pool: &MySqlPool, timeout: Duration) -> Result<(), Error> {
+    tokio::time::timeout(timeout, health_check(pool))
+        .await
+        .unwrap_or_else(|_| {
+            Err(Error::Io(io::Error::new(
+                io::ErrorKind::TimedOut,
+                format!("TiDB health check did not complete within {:?}", timeout),
+            )))
+        })
+}
+
+/// Checks that the pool can prepare and execute a parameterized statement.
+///
+/// Runs `SELECT ?` bound to a known value and compares the echoed result, so parameter binding
+/// and decoding are checked end-to-end, which a bare `SELECT 1` does not cover. A mismatch is
+/// reported as [`Error::Protocol`].
+///
+/// ## Example:
+/// ```rust,ignore
+/// deep_health_check(&pool).await?;
+/// ```
+pub async fn deep_health_check(pool: &MySqlPool) -> Result<(), Error> {
+    const PROBE: i64 = 4_000_042;
+    let echoed: i64 = sqlx::query_scalar("SELECT ?").bind(PROBE).fetch_one(pool).await?;
+    if echoed != PROBE {
+        return Err(Error::Protocol(format!(
+            "deep health check returned {} instead of {}",
+            echoed, PROBE
+        )));
+    }
+    Ok(())
+}
+
+/// Collects at most `max_rows` items from a row stream.
+async fn collect_capped<T, S>(stream: S, max_rows: usize) -> Result<Vec<T>, Error>
+where
+    S: Stream<Item = Result<T, Error>>,
+{
+    let mut stream = std::pin::pin!(stream);
+    let mut rows = Vec::new();
+
+    while let Some(row) = stream.try_next().await? {
+        if rows.len() == max_rows {
+            warn!("Result set exceeded the streaming cap of {} rows; truncating", max_rows);
+            break;
+        }
+        rows.push(row);
+    }
+
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        build_pool_from_config,
+        test_support::{capture_logs, integration_config},
+    };
+
+    /// Test that the streaming path stops accumulating at the row cap.
+    #[tokio::test]
+    async fn test_collect_capped_respects_row_cap() {
+        let stream = futures_util::stream::iter((0..100).map(Ok::<_, Error>));
+
+        let rows = collect_capped(stream, 10).await.expect("Failed to collect rows");
+
+        assert_eq!(rows, (0..10).collect::<Vec<_>>());
+    }
+
+    /// Test that the streaming path keeps every row when the cap is not reached.
+    #[tokio::test]
+    async fn test_collect_capped_below_cap() {
+        let stream = futures_util::stream::iter((0..5).map(Ok::<_, Error>));
+
+        let rows = collect_capped(stream, 10).await.expect("Failed to collect rows");
+
+        assert_eq!(rows.len(), 5);
+    }
+
+    /// Test that invalid column names are rejected before any query is sent.
+    #[tokio::test]
+    async fn test_count_distinct_rejects_bad_column() {
+        let pool = sqlx::MySqlPool::connect_lazy("mysql://root@127.0.0.1:1/test")
+            .expect("Failed to build lazy pool");
+        let table = TableName::new("users").unwrap();
+
+        let err = count_distinct(&pool, &table, "id) FROM users; --")
+            .await
+            .expect_err("Bad column should be rejected");
+
+        assert!(matches!(err, Error::InvalidArgument(_)));
+    }
+
+    /// Test that a delete without a `WHERE` clause is refused before any query is sent.
+    #[tokio::test]
+    async fn test_delete_where_requires_where_clause() {
+        let pool = sqlx::MySqlPool::connect_lazy("mysql://root@127.0.0.1:1/test")
+            .expect("Failed to build lazy pool");
+        let table = TableName::new("users").unwrap();
+
+        for where_clause in ["", "   "] {
+            let err = delete_where(&pool, &table, where_clause, MySqlArguments::default())
+                .await
+                .expect_err("Full-table delete should be refused");
+
+            let Error::Database(err) = err else {
+                panic!("unexpected error: {:?}", err);
+            };
+            let err = err
+                .try_downcast_ref::<MissingWhereClause>()
+                .expect("Expected a missing WHERE clause");
+            assert_eq!(err.table(), table.to_string());
+        }
+    }
+
+    /// Test that `delete_where` removes only the matching rows and reports how many.
+    #[tokio::test]
+    #[ignore = "requires a running TiDB instance"]
+    async fn test_delete_where() {
+        use sqlx::Arguments;
+
+        let pool = build_pool_from_config(integration_config())
+            .await
+            .expect("Failed to build pool");
+        sqlx::raw_sql(
+            "DROP TABLE IF EXISTS tidb_pool_delete_where;
+             CREATE TABLE tidb_pool_delete_where (id INT PRIMARY KEY, expired TINYINT);
+             INSERT INTO tidb_pool_delete_where VALUES (1, 1), (2, 1), (3, 0);",
+        )
+        .execute(&pool)
+        .await
+        .expect("Failed to create table");
+        let table = TableName::new("tidb_pool_delete_where").unwrap();
+        let mut args = MySqlArguments::default();
+        args.add(1).unwrap();
+
+        let deleted = delete_where(&pool, &table, "expired = ?", args).await.unwrap();
+        let remaining = count_rows(&pool, &table).await.unwrap();
+
+        assert_eq!(*deleted, 2);
+        assert_eq!(*remaining, 1);
+    }
+
+    /// Test that warnings and errors are formatted while notes are left out.
+    #[test]
+    fn test_format_warning_skips_notes() {
+        assert_eq!(
+            format_warning("Warning", 1265, "Data truncated for column 'qty' at row 1").as_deref(),
+            Some("Warning 1265: Data truncated for column 'qty' at row 1")
+        );
+        assert_eq!(
+            format_warning("Error", 1366, "Incorrect integer value").as_deref(),
+            Some("Error 1366: Incorrect integer value")
+        );
+        assert_eq!(format_warning("Note", 1051, "Unknown table 'test.t'"), None);
+    }
+
+    /// Test that the affected rows and the warnings of a statement are returned together.
+    #[tokio::test]
+    #[ignore = "requires a running TiDB instance"]
+    async fn test_execute_with_warnings() {
+        let pool = build_pool_from_config(integration_config())
+            .await
+            .expect("Failed to build pool");
+        sqlx::raw_sql(
+            "DROP TABLE IF EXISTS tidb_pool_warnings;
+             CREATE TABLE tidb_pool_warnings (id INT PRIMARY KEY, qty TINYINT);
+             INSERT INTO tidb_pool_warnings VALUES (1, 1), (2, 2);",
+        )
+        .execute(&pool)
+        .await
+        .expect("Failed to create table");
+
+        // Comparing an integer column with `'1abc'` truncates the string, with a warning
+        let (updated, warnings) =
+            execute_with_warnings(&pool, "UPDATE tidb_pool_warnings SET qty = 0 WHERE id = '1abc'")
+                .await
+                .expect("Failed to execute");
+        assert_eq!(*updated, 1);
+        assert!(
+            warnings.iter().any(|warning| warning.contains("1abc")),
+            "{:?}",
+            warnings
+        );
+
+        let (updated, warnings) =
+            execute_with_warnings(&pool, "UPDATE tidb_pool_warnings SET qty = 3 WHERE id = 2")
+                .await
+                .expect("Failed to execute");
+        assert_eq!(*updated, 1);
+        assert!(warnings.is_empty(), "{:?}", warnings);
+    }
+
+    /// Test `table_exists` for an existing and a nonexistent table.
+    #[tokio::test]
+    #[ignore = "requires a running TiDB instance"]
+    async fn test_table_exists() {
+        let pool = build_pool_from_config(integration_config())
+            .await
+            .expect("Failed to build pool");
+        sqlx::raw_sql("CREATE TABLE IF NOT EXISTS tidb_pool_table_exists (id INT PRIMARY KEY)")
+            .execute(&pool)
+            .await
+            .expect("Failed to create table");
+
+        let existing = TableName::new("tidb_pool_table_exists").unwrap();
+        let missing = TableName::new("tidb_pool_table_missing").unwrap();
+
+        assert!(table_exists(&pool, &existing).await.unwrap());
+        assert!(!table_exists(&pool, &missing).await.unwrap());
+    }
+
+    /// Test `count_distinct` against a real table.
+    #[tokio::test]
+    #[ignore = "requires a running TiDB instance"]
+    async fn test_count_distinct() {
+        let pool = build_pool_from_config(integration_config())
+            .await
+            .expect("Failed to build pool");
+        sqlx::raw_sql(
+            "DROP TABLE IF EXISTS tidb_pool_count_distinct;
+             CREATE TABLE tidb_pool_count_distinct (id INT PRIMARY KEY, color VARCHAR(16));
+             INSERT INTO tidb_pool_count_distinct VALUES (1, 'red'), (2, 'red'), (3, 'blue'), (4, NULL);",
+        )
+        .execute(&pool)
+        .await
+        .expect("Failed to create table");
+        let table = TableName::new("tidb_pool_count_distinct").unwrap();
+
+        let distinct = count_distinct(&pool, &table, "color").await.unwrap();
+        let total = count_rows(&pool, &table).await.unwrap();
+
+        assert_eq!(*distinct, 2);
+        assert_eq!(*total, 4);
+    }
+
+    /// Test that `fetch_sum` maps a `NULL` sum over zero rows to zero and sums other rows.
+    #[tokio::test]
+    #[ignore = "requires a running TiDB instance"]
+    async fn test_fetch_sum() {
+        let pool = build_pool_from_config(integration_config())
+            .await
+            .expect("Failed to build pool");
+        sqlx::raw_sql(
+            "DROP TABLE IF EXISTS tidb_pool_fetch_sum;
+             CREATE TABLE tidb_pool_fetch_sum (id INT PRIMARY KEY, amount INT);
+             INSERT INTO tidb_pool_fetch_sum VALUES (1, 10), (2, 32);",
+        )
+        .execute(&pool)
+        .await
+        .expect("Failed to create table");
+
+        let empty = fetch_sum(&pool, "SELECT SUM(amount) FROM tidb_pool_fetch_sum WHERE id > 100")
+            .await
+            .expect("Failed to sum empty set");
+        let total = fetch_sum(&pool, "SELECT SUM(amount) FROM tidb_pool_fetch_sum")
+            .await
+            .expect("Failed to sum rows");
+
+        assert_eq!(*empty, 0);
+        assert_eq!(*total, 42);
+    }
+
+    /// Test that the buffered path returns every row.
+    #[tokio::test]
+    #[ignore = "requires a running TiDB instance"]
+    async fn test_fetch_rows_buffered_returns_all_rows() {
+        let pool = build_pool_from_config(integration_config())
+            .await
+            .expect("Failed to build pool");
+
+        let rows: Vec<(i64,)> = fetch_rows(
+            &pool,
+            "SELECT 1 UNION ALL SELECT 2 UNION ALL SELECT 3",
+            FetchMode::Buffered,
+        )
+        .await
+        .expect("Failed to fetch rows");
+
+        assert_eq!(rows.len(), 3);
+    }
+
+    /// Test that the number of returned rows is logged as a structured `rows` field.
+    #[tokio::test]
+    #[ignore = "requires a running TiDB instance"]
+    async fn test_fetch_rows_logs_row_count() {
+        let pool = build_pool_from_config(integration_config())
+            .await
+            .expect("Failed to build pool");
+        let (_guard, logs) = capture_logs();
+
+        let _: Vec<(i64,)> = fetch_rows(
+            &pool,
+            "SELECT 1 UNION ALL SELECT 2 UNION ALL SELECT 3",
+            FetchMode::Buffered,
+        )
+        .await
+        .expect("Failed to fetch rows");
+
+        assert!(logs.contents().contains("rows=3"), "{}", logs.contents());
+    }
+
+    /// Test that `find_one` decodes a matching row and returns `None` when nothing matches.
+    #[tokio::test]
+    #[ignore = "requires a running TiDB instance"]
+    async fn test_find_one_found_and_not_found() {
+        use sqlx::Arguments;
+
+        #[derive(sqlx::FromRow)]
+        struct Item {
+            id: i32,
+            name: String,
+        }
+
+        let pool = build_pool_from_config(integration_config())
+            .await
+            .expect("Failed to build pool");
+        sqlx::raw_sql(
+            "DROP TABLE IF EXISTS tidb_pool_find_one;
+             CREATE TABLE tidb_pool_find_one (id INT PRIMARY KEY, name VARCHAR(32));
+             INSERT INTO tidb_pool_find_one VALUES (1, 'first');",
+        )
+        .execute(&pool)
+        .await
+        .expect("Failed to create table");
+        let query = "SELECT id, name FROM tidb_pool_find_one WHERE id = ?";
+
+        let mut args = MySqlArguments::default();
+        args.add(1).unwrap();
+        let found: Option<Item> = find_one(&pool, query, args).await.unwrap();
+        let found = found.expect("Row should be found");
+        assert_eq!((found.id, found.name.as_str()), (1, "first"));
+
+        let mut args = MySqlArguments::default();
+        args.add(2).unwrap();
+        let missing: Option<Item> = find_one(&pool, query, args).await.unwrap();
+        assert!(missing.is_none());
+    }
+
+    /// Test that the server clock is close to the local clock.
+    #[cfg(feature = "chrono")]
+    #[tokio::test]
+    #[ignore = "requires a running TiDB instance"]
+    async fn test_server_now_close_to_local_time() {
+        let pool = build_pool_from_config(integration_config())
+            .await
+            .expect("Failed to build pool");
+
+        let server = server_now(&pool).await.expect("Failed to read server time");
+        let skew = chrono::Utc::now() - server;
+
+        assert!(skew.num_seconds().abs() < 5, "clock skew too large: {}", skew);
+    }
+
+    /// Test that renaming a table to itself is refused before any query is sent.
+    #[cfg(feature = "unsafe_admin")]
+    #[tokio::test]
+    async fn test_rename_table_to_itself() {
+        let pool = sqlx::MySqlPool::connect_lazy("mysql://root@127.0.0.1:1/test")
+            .expect("Failed to build lazy pool");
+        let table = TableName::new("users").unwrap();
+
+        let err = rename_table(&pool, &table, &table)
+            .await
+            .expect_err("Rename to itself should be refused");
+
+        assert!(matches!(err, Error::InvalidArgument(_)));
+    }
+
+    /// Test that a renamed table exists under its new name only.
+    #[cfg(feature = "unsafe_admin")]
+    #[tokio::test]
+    #[ignore = "requires a running TiDB instance"]
+    async fn test_rename_table() {
+        let pool = build_pool_from_config(integration_config())
+            .await
+            .expect("Failed to build pool");
+        sqlx::raw_sql(
+            "DROP TABLE IF EXISTS tidb_pool_rename_from, tidb_pool_rename_to;
+             CREATE TABLE tidb_pool_rename_from (id INT PRIMARY KEY);",
+        )
+        .execute(&pool)
+        .await
+        .expect("Failed to create table");
+        let from = TableName::new("tidb_pool_rename_from").unwrap();
+        let to = TableName::new("tidb_pool_rename_to").unwrap();
+
+        rename_table(&pool, &from, &to).await.expect("Failed to rename table");
+
+        assert!(table_exists(&pool, &to).await.unwrap());
+        assert!(!table_exists(&pool, &from).await.unwrap());
+    }
+
+    /// Test that the streaming path respects the row cap against a real server.
+    #[tokio::test]
+    #[ignore = "requires a running TiDB instance"]
+    async fn test_fetch_rows_streaming_respects_cap() {
+        let pool = build_pool_from_config(integration_config())
+            .await
+            .expect("Failed to build pool");
+
+        let rows: Vec<(i64,)> = fetch_rows(
+            &pool,
+            "SELECT 1 UNION ALL SELECT 2 UNION ALL SELECT 3",
+            FetchMode::Streaming { max_rows: 2 },
+        )
+        .await
+        .expect("Failed to fetch rows");
+
+        assert_eq!(rows.len(), 2);
+    }
+
+    /// Test that the health check succeeds against a running server.
+    #[tokio::test]
+    #[ignore = "requires a running TiDB instance"]
+    async fn test_health_check() {
+        let pool = build_pool_from_config(integration_config())
+            .await
+            .expect("Failed to build pool");
+
+        health_check(&pool).await.expect("Health check failed");
+        health_check_with_timeout(&pool, Duration::from_secs(5))
+            .await
+            .expect("Health check failed");
+    }
+
+    /// Test that a health check against a server that never answers times out descriptively.
+    #[tokio::test]
+    async fn test_health_check_with_timeout_elapses() {
+        // A server that accepts connections but never sends the handshake.
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("mysql://root@{}/test", listener.local_addr().unwrap());
+        let pool = sqlx::MySqlPool::connect_lazy(&url).expect("Failed to build lazy pool");
+
+        let err = health_check_with_timeout(&pool, Duration::from_millis(200))
+            .await
+            .expect_err("Health check should time out");
+
+        let Error::Io(err) = err else {
+            panic!("unexpected error: {:?}", err);
+        };
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+        assert_eq!(err.to_string(), "TiDB health check did not complete within 200ms");
+    }
+
+    /// Test that a parameterized statement round-trips through the pool.
+    #[tokio::test]
+    #[ignore = "requires a running TiDB instance"]
+    async fn test_deep_health_check() {
+        let pool = build_pool_from_config(integration_config())
+            .await
+            .expect("Failed to build pool");
+
+        deep_health_check(&pool).await.expect("Deep health check failed");
+    }
+
+    /// Test that `assert_count` accepts the actual row count and panics on a mismatch.
+    #[tokio::test]
+    #[ignore = "requires a running TiDB instance"]
+    async fn test_assert_count() {
+        let pool = build_pool_from_config(integration_config())
+            .await
+            .expect("Failed to build pool");
+        sqlx::raw_sql(
+            "DROP TABLE IF EXISTS tidb_pool_assert_count;
+             CREATE TABLE tidb_pool_assert_count (id INT);
+             INSERT INTO tidb_pool_assert_count VALUES (1), (2);",
+        )
+        .execute(&pool)
+        .await
+        .expect("Failed to create table");
+        let table = TableName::new("tidb_pool_assert_count").unwrap();
+
+        assert_count(&pool, &table, 2).await;
+
+        let mismatch = tokio::spawn(async move { assert_count(&pool, &table, 3).await }).await;
+        let panic = mismatch.expect_err("Mismatch should panic").into_panic();
+        let message = panic.downcast_ref::<String>().expect("Panic message");
+        assert!(message.contains("to hold 3 rows, found 2"), "{}", message);
+    }
+}
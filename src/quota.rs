@@ -0,0 +1,88 @@
+//! Quotas shared between processes, enforced through counter rows in the database.
+
+use sqlx::{Error, MySqlPool};
+
+use crate::{count::Count, transaction::with_transaction};
+
+/// Table holding the quota counters used by [`try_consume_quota`].
+///
+/// It must be created beforehand with the following schema:
+///
+/// ```sql
+/// CREATE TABLE tidb_pool_quotas (
+///     quota_key VARCHAR(255) NOT NULL PRIMARY KEY,
+///     used BIGINT NOT NULL DEFAULT 0
+/// );
+/// ```
+pub const QUOTA_TABLE: &str = "tidb_pool_quotas";
+
+/// Consumes one unit of the quota identified by `key` if fewer than `limit` units were used.
+///
+/// Returns `true` if a unit was consumed and `false` if the quota is exhausted. The counter row
+/// is created on first use, then read with `SELECT ... FOR UPDATE` and incremented in the same
+/// transaction, so concurrent callers across processes never exceed `limit`. Counters are never
+/// reset by this function; delete or update the row in [`QUOTA_TABLE`] to start a new window.
+///
+/// ## Example:
+/// ```rust,ignore
+/// if !try_consume_quota(&pool, "exports:tenant-42", 100).await? {
+///     return Err(ApiError::TooManyRequests);
+/// }
+/// ```
+pub async fn try_consume_quota(pool: &MySqlPool, key: &str, limit: i64) -> Result<bool, Error> {
+    let key = key.to_owned();
+    with_transaction(pool, move |tx| {
+        Box::pin(async move {
+            let insert =
+                format!("INSERT IGNORE INTO {} (quota_key, used) VALUES (?, 0)", QUOTA_TABLE);
+            sqlx::query(&insert).bind(&key).execute(&mut **tx).await?;
+
+            let select = format!("SELECT used FROM {} WHERE quota_key = ? FOR UPDATE", QUOTA_TABLE);
+            let used: Count = sqlx::query_as(&select).bind(&key).fetch_one(&mut **tx).await?;
+            if *used >= limit {
+                return Ok(false);
+            }
+
+            let update = format!("UPDATE {} SET used = used + 1 WHERE quota_key = ?", QUOTA_TABLE);
+            sqlx::query(&update).bind(&key).execute(&mut **tx).await?;
+            Ok(true)
+        })
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{build_pool_from_config, test_support::integration_config};
+
+    /// Test that units are consumed up to the limit and refused afterwards.
+    #[tokio::test]
+    #[ignore = "requires a running TiDB instance"]
+    async fn test_try_consume_quota_until_limit() {
+        let pool = build_pool_from_config(integration_config())
+            .await
+            .expect("Failed to build pool");
+        sqlx::raw_sql(
+            "CREATE TABLE IF NOT EXISTS tidb_pool_quotas (
+                 quota_key VARCHAR(255) NOT NULL PRIMARY KEY,
+                 used BIGINT NOT NULL DEFAULT 0
+             );
+             DELETE FROM tidb_pool_quotas WHERE quota_key = 'test-quota';",
+        )
+        .execute(&pool)
+        .await
+        .expect("Failed to create table");
+
+        assert!(try_consume_quota(&pool, "test-quota", 2).await.unwrap());
+        assert!(try_consume_quota(&pool, "test-quota", 2).await.unwrap());
+        assert!(!try_consume_quota(&pool, "test-quota", 2).await.unwrap());
+
+        let used: Count = sqlx::query_as("SELECT used FROM tidb_pool_quotas WHERE quota_key = ?")
+            .bind("test-quota")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(*used, 2);
+    }
+}
@@ -0,0 +1,118 @@
+//! Read/write splitting between a primary pool and read replicas.
+
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use futures_util::future::join_all;
+use sqlx::{mysql::MySqlRow, Error, FromRow, MySqlPool};
+
+/// A primary pool for writes paired with replica pools for reads.
+///
+/// Cloning is cheap and clones share the same pools and round-robin position.
+#[derive(Clone, Debug)]
+pub struct ReadWritePool {
+    primary: MySqlPool,
+    replicas: Vec<MySqlPool>,
+    next_replica: Arc<AtomicUsize>,
+}
+
+impl ReadWritePool {
+    /// Creates a read/write pool from a primary and any number of replicas.
+    pub fn new(primary: MySqlPool, replicas: Vec<MySqlPool>) -> Self {
+        ReadWritePool {
+            primary,
+            replicas,
+            next_replica: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Pool used for writes and reads that must see the latest data.
+    pub fn primary(&self) -> &MySqlPool {
+        &self.primary
+    }
+
+    /// All replica pools, in the order they were configured.
+    pub fn replicas(&self) -> &[MySqlPool] {
+        &self.replicas
+    }
+
+    /// Pool to use for a read, picking replicas round-robin.
+    ///
+    /// Falls back to the primary when no replicas are configured.
+    pub fn reader(&self) -> &MySqlPool {
+        if self.replicas.is_empty() {
+            return &self.primary;
+        }
+        let index = self.next_replica.fetch_add(1, Ordering::Relaxed) % self.replicas.len();
+        &self.replicas[index]
+    }
+}
+
+/// Runs the same read on every replica concurrently and returns one result per replica.
+///
+/// Results are in the same order as [`ReadWritePool::replicas`]. A failure on one replica does
+/// not short-circuit the others: every replica is queried and its error, if any, is returned in
+/// its slot. Useful for cache warming or comparing replicas (e.g. checking replication lag).
+///
+/// ## Example:
+/// ```rust,ignore
+/// let results: Vec<Result<(i64,), sqlx::Error>> =
+///     query_all_replicas(&rw, "SELECT MAX(id) FROM events").await;
+/// ```
+pub async fn query_all_replicas<T>(rw: &ReadWritePool, query: &str) -> Vec<Result<T, Error>>
+where
+    T: for<'r> FromRow<'r, MySqlRow> + Send + Unpin,
+{
+    join_all(
+        rw.replicas()
+            .iter()
+            .map(|replica| sqlx::query_as::<_, T>(query).fetch_one(replica)),
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use sqlx::mysql::{MySqlConnectOptions, MySqlPoolOptions};
+
+    use super::*;
+
+    /// Builds a lazy pool pointing at a port nothing listens on.
+    fn stub_pool() -> MySqlPool {
+        MySqlPoolOptions::new()
+            .acquire_timeout(Duration::from_millis(500))
+            .connect_lazy_with(MySqlConnectOptions::new().host("127.0.0.1").port(1))
+    }
+
+    /// Test that every replica is queried and failures are returned rather than short-circuited.
+    #[tokio::test]
+    async fn test_query_all_replicas_returns_per_replica_results() {
+        let rw = ReadWritePool::new(stub_pool(), vec![stub_pool(), stub_pool()]);
+
+        let results: Vec<Result<(i64,), Error>> = query_all_replicas(&rw, "SELECT 1").await;
+
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(Result::is_err));
+    }
+
+    /// Test that reads rotate over replicas and fall back to the primary.
+    #[tokio::test]
+    async fn test_reader_round_robin() {
+        let rw = ReadWritePool::new(stub_pool(), vec![stub_pool(), stub_pool()]);
+        let first = rw.reader().connect_options();
+        let second = rw.reader().connect_options();
+        let third = rw.reader().connect_options();
+        assert!(!Arc::ptr_eq(&first, &second));
+        assert!(Arc::ptr_eq(&first, &third));
+
+        let primary_only = ReadWritePool::new(stub_pool(), Vec::new());
+        assert!(Arc::ptr_eq(
+            &primary_only.reader().connect_options(),
+            &primary_only.primary().connect_options()
+        ));
+    }
+}
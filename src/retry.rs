@@ -0,0 +1,136 @@
+//! Helpers for recovering from transient connection failures.
+
+use std::io;
+
+use futures_util::future::BoxFuture;
+use sqlx::{mysql::MySqlDatabaseError, Error, MySqlConnection, MySqlPool};
+
+/// MySQL client error: the server has gone away.
+const CR_SERVER_GONE_ERROR: u16 = 2006;
+/// MySQL client error: lost connection to the server during a query.
+const CR_SERVER_LOST: u16 = 2013;
+/// MySQL server error: the server is shutting down.
+const ER_SERVER_SHUTDOWN: u16 = 1053;
+
+/// Returns `true` if `err` indicates that the connection to the server was lost.
+///
+/// Covers socket-level failures (reset, aborted, broken pipe, unexpected EOF) as well as the
+/// MySQL "server has gone away" / "lost connection" / "server shutdown" error codes.
+pub fn is_connection_loss(err: &Error) -> bool {
+    match err {
+        Error::Io(io_err) => matches!(
+            io_err.kind(),
+            io::ErrorKind::ConnectionReset
+                | io::ErrorKind::ConnectionAborted
+                | io::ErrorKind::BrokenPipe
+                | io::ErrorKind::UnexpectedEof
+                | io::ErrorKind::NotConnected
+        ),
+        Error::Database(db_err) => db_err
+            .try_downcast_ref::<MySqlDatabaseError>()
+            .is_some_and(|mysql_err| {
+                matches!(
+                    mysql_err.number(),
+                    CR_SERVER_GONE_ERROR | CR_SERVER_LOST | ER_SERVER_SHUTDOWN
+                )
+            }),
+        _ => false,
+    }
+}
+
+/// Runs `f` on a pooled connection, retrying once on a fresh connection if the first one is lost.
+///
+/// When `f` fails with a connection-loss error (see [`is_connection_loss`]) and `idempotent` is
+/// `true`, the broken connection is discarded, a new one is acquired and `f` is run a second
+/// time. Any other error, or a second failure, is returned as-is.
+///
+/// **Only pass `idempotent = true` when running `f` twice is safe.** A connection can drop after
+/// the server has applied a statement but before the client saw the result, so non-idempotent
+/// work (e.g. an `INSERT` outside a transaction) may be applied twice if retried.
+///
+/// ## Example:
+/// ```rust,ignore
+/// let count: i64 = with_retry_conn(&pool, true, |conn| {
+///     Box::pin(async move {
+///         sqlx::query_scalar("SELECT COUNT(*) FROM users").fetch_one(conn).await
+///     })
+/// })
+/// .await?;
+/// ```
+pub async fn with_retry_conn<F, T>(pool: &MySqlPool, idempotent: bool, mut f: F) -> Result<T, Error>
+where
+    F: for<'c> FnMut(&'c mut MySqlConnection) -> BoxFuture<'c, Result<T, Error>>,
+{
+    let mut conn = pool.acquire().await?;
+    match f(&mut conn).await {
+        Err(err) if idempotent && is_connection_loss(&err) => {
+            warn!("Connection to TiDB lost ({}); retrying on a fresh connection", err);
+            // Make sure the broken connection is not handed out again.
+            conn.close_on_drop();
+            drop(conn);
+
+            let mut conn = pool.acquire().await?;
+            f(&mut conn).await
+        }
+        result => result,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{build_pool_from_config, test_support::integration_config};
+
+    /// Test that socket-level failures are classified as connection loss.
+    #[test]
+    fn test_is_connection_loss_io_errors() {
+        let reset = Error::Io(io::Error::from(io::ErrorKind::ConnectionReset));
+        let interrupted = Error::Io(io::Error::from(io::ErrorKind::Interrupted));
+
+        assert!(is_connection_loss(&reset));
+        assert!(!is_connection_loss(&interrupted));
+        assert!(!is_connection_loss(&Error::RowNotFound));
+    }
+
+    /// Test that a simulated dropped connection is retried once on a fresh connection.
+    #[tokio::test]
+    #[ignore = "requires a running TiDB instance"]
+    async fn test_with_retry_conn_retries_after_connection_loss() {
+        let pool = build_pool_from_config(integration_config())
+            .await
+            .expect("Failed to build pool");
+        let mut attempts = 0;
+
+        let value: i64 = with_retry_conn(&pool, true, |conn| {
+            attempts += 1;
+            let first = attempts == 1;
+            Box::pin(async move {
+                if first {
+                    return Err(Error::Io(io::Error::from(io::ErrorKind::ConnectionReset)));
+                }
+                sqlx::query_scalar("SELECT 1").fetch_one(conn).await
+            })
+        })
+        .await
+        .expect("Retry should succeed");
+
+        assert_eq!(value, 1);
+        assert_eq!(attempts, 2);
+    }
+
+    /// Test that non-idempotent operations are not retried.
+    #[tokio::test]
+    #[ignore = "requires a running TiDB instance"]
+    async fn test_with_retry_conn_skips_retry_when_not_idempotent() {
+        let pool = build_pool_from_config(integration_config())
+            .await
+            .expect("Failed to build pool");
+
+        let result: Result<(), Error> = with_retry_conn(&pool, false, |_conn| {
+            Box::pin(async { Err(Error::Io(io::Error::from(io::ErrorKind::ConnectionReset))) })
+        })
+        .await;
+
+        assert!(result.is_err());
+    }
+}
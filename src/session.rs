@@ -0,0 +1,692 @@
+//! Session settings applied to every new connection through an `after_connect` hook.
+
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use futures_util::future::BoxFuture;
+use sqlx::{
+    mysql::MySqlPoolOptions, pool::PoolConnection, Error, Executor, MySql, MySqlConnection,
+    MySqlPool,
+};
+
+use crate::{config::TiDBConfig, hooks::PoolHooks, watchdog::ConnectionIds};
+
+/// Typed TiDB boolean session variables.
+///
+/// Each flag left unset keeps the server default.
+///
+/// # Example (TOML)
+/// ```toml
+/// [session_flags]
+/// tidbEnablePaging = true
+/// tidbEnableClusteredIndex = false
+/// ```
+#[derive(Clone, Default, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct TiDBSessionFlags {
+    /// Sets `tidb_enable_paging`, which lets coprocessor requests be sent in pages to reduce
+    /// memory usage for large scans.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tidb_enable_paging: Option<bool>,
+
+    /// Sets `tidb_enable_clustered_index`, which controls whether new tables created on this
+    /// connection use clustered primary keys by default.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tidb_enable_clustered_index: Option<bool>,
+}
+
+impl TiDBSessionFlags {
+    /// Returns `true` if no flag is set.
+    pub fn is_empty(&self) -> bool {
+        self.tidb_enable_paging.is_none() && self.tidb_enable_clustered_index.is_none()
+    }
+
+    fn statements(&self) -> impl Iterator<Item = String> + '_ {
+        [
+            ("tidb_enable_paging", self.tidb_enable_paging),
+            ("tidb_enable_clustered_index", self.tidb_enable_clustered_index),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| {
+            value.map(|value| format!("SET SESSION {} = {}", name, on_off(value)))
+        })
+    }
+}
+
+/// Connection character set, collation, `sql_mode` and time zone, applied together with
+/// [`TiDBConfig::with_session_profile`].
+///
+/// Each setting left unset keeps the value already in the configuration.
+///
+/// ## Example:
+/// ```rust
+/// use tidb_pool::{SessionProfile, TiDBConfig};
+///
+/// let profile = SessionProfile {
+///     sql_mode: Some("STRICT_TRANS_TABLES,NO_ENGINE_SUBSTITUTION".into()),
+///     time_zone: Some("+00:00".into()),
+///     ..SessionProfile::default()
+/// };
+/// let config = TiDBConfig::default().with_session_profile(profile);
+/// assert_eq!(config.time_zone.as_deref(), Some("+00:00"));
+/// ```
+#[derive(Clone, Default, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionProfile {
+    /// See [`TiDBConfig::charset`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub charset: Option<String>,
+
+    /// See [`TiDBConfig::collation`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub collation: Option<String>,
+
+    /// See [`TiDBConfig::sql_mode`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sql_mode: Option<String>,
+
+    /// See [`TiDBConfig::time_zone`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_zone: Option<String>,
+}
+
+impl SessionProfile {
+    /// The settings of a default MySQL 8.0 server: `utf8mb4` with `utf8mb4_general_ci`, the
+    /// MySQL 8.0 default `sql_mode` and UTC.
+    ///
+    /// Useful to make a TiDB cluster behave like the MySQL server an application was written
+    /// against, whatever the cluster's own defaults are.
+    pub fn mysql_compat() -> SessionProfile {
+        SessionProfile {
+            charset: Some("utf8mb4".into()),
+            collation: Some("utf8mb4_general_ci".into()),
+            sql_mode: Some(
+                "ONLY_FULL_GROUP_BY,STRICT_TRANS_TABLES,NO_ZERO_IN_DATE,NO_ZERO_DATE,\
+                 ERROR_FOR_DIVISION_BY_ZERO,NO_ENGINE_SUBSTITUTION"
+                    .into(),
+            ),
+            time_zone: Some("+00:00".into()),
+        }
+    }
+}
+
+impl TiDBConfig {
+    /// Applies every setting of `profile` to this configuration.
+    ///
+    /// The character set and collation are negotiated when each connection is opened, and the
+    /// `sql_mode` and time zone are set together, in a single `SET SESSION` statement, by the
+    /// same `after_connect` hook as the other session settings, so a connection that fails to
+    /// apply any of them is rejected. Settings the profile leaves unset are not changed.
+    pub fn with_session_profile(mut self, profile: SessionProfile) -> Self {
+        let SessionProfile {
+            charset,
+            collation,
+            sql_mode,
+            time_zone,
+        } = profile;
+        for (field, value) in [
+            (&mut self.charset, charset),
+            (&mut self.collation, collation),
+            (&mut self.sql_mode, sql_mode),
+            (&mut self.time_zone, time_zone),
+        ] {
+            if value.is_some() {
+                *field = value;
+            }
+        }
+        self
+    }
+}
+
+fn on_off(value: bool) -> &'static str {
+    if value {
+        "ON"
+    } else {
+        "OFF"
+    }
+}
+
+/// Returns the SQL statements run, in order, on every new connection.
+pub(crate) fn session_init_statements(config: &TiDBConfig) -> Vec<String> {
+    let mut statements: Vec<String> = config.session_flags.statements().collect();
+    if let Some(max_len) = config.group_concat_max_len {
+        statements.push(format!("SET SESSION group_concat_max_len = {}", max_len));
+    }
+    // The time zone and `sql_mode` share a statement, saving a round trip per connection
+    let variables = [("time_zone", &config.time_zone), ("sql_mode", &config.sql_mode)];
+    let assignments: Vec<String> = variables
+        .into_iter()
+        .filter_map(|(name, value)| {
+            value
+                .as_deref()
+                .map(|value| format!("{} = {}", name, quote_string(value)))
+        })
+        .collect();
+    if !assignments.is_empty() {
+        statements.push(format!("SET SESSION {}", assignments.join(", ")));
+    }
+    if let Some(init_command) = config.init_command.as_deref() {
+        if !init_command.trim().is_empty() {
+            statements.push(init_command.to_owned());
+        }
+    }
+    statements.extend(
+        config
+            .pool_options
+            .init_sql
+            .iter()
+            .filter(|statement| !statement.trim().is_empty())
+            .cloned(),
+    );
+    statements
+}
+
+/// Quotes `value` as a SQL string literal.
+fn quote_string(value: &str) -> String {
+    format!("'{}'", value.replace('\\', "\\\\").replace('\'', "''"))
+}
+
+/// Registers an `after_connect` hook running the session statements for `config`, then the
+/// connection callbacks of `hooks`, and recording the connection id in `connection_ids`.
+///
+/// If any statement fails, the new connection is rejected and the error is propagated.
+/// `pool_options` is returned unchanged when there is nothing to run.
+pub(crate) fn install_session_hook(
+    pool_options: MySqlPoolOptions,
+    config: &TiDBConfig,
+    hooks: &PoolHooks,
+    connection_ids: Option<&ConnectionIds>,
+) -> MySqlPoolOptions {
+    let statements = session_init_statements(config);
+    let lifecycle = lifecycle_pool_name(config);
+    if statements.is_empty() && hooks.is_empty() && lifecycle.is_none() && connection_ids.is_none()
+    {
+        return pool_options;
+    }
+
+    let statements = Arc::new(statements);
+    let on_initialized = hooks.on_connection_initialized.clone();
+    let connection_ids = connection_ids.cloned();
+    pool_options.after_connect(move |conn, _meta| {
+        let statements = Arc::clone(&statements);
+        let on_initialized = on_initialized.clone();
+        let lifecycle = lifecycle.clone();
+        let connection_ids = connection_ids.clone();
+        Box::pin(async move {
+            let started = Instant::now();
+            for statement in statements.iter() {
+                conn.execute(statement.as_str()).await?;
+            }
+            // Without the id, the query watchdog only skips this connection
+            if let Some(connection_ids) = connection_ids {
+                match connection_id(conn).await {
+                    Ok(id) => connection_ids.insert(id),
+                    Err(err) => warn!("Failed to read the TiDB connection id: {}", err),
+                }
+            }
+            if let Some(on_initialized) = on_initialized {
+                on_initialized(started.elapsed());
+            }
+            if let Some(pool) = lifecycle {
+                trace_lifecycle(&pool, conn, "connected").await;
+            }
+            Ok(())
+        })
+    })
+}
+
+/// Returns the pool name used in lifecycle events, or `None` if `verbose_lifecycle` is off.
+pub(crate) fn lifecycle_pool_name(config: &TiDBConfig) -> Option<Arc<str>> {
+    config
+        .pool_options
+        .verbose_lifecycle
+        .then(|| Arc::from(config.pool_name()))
+}
+
+/// Logs a connection lifecycle `event` at `TRACE` level with the pool name and connection id.
+///
+/// The id is only looked up when `TRACE` events are enabled. Failing to look it up is logged
+/// rather than returned, so tracing never rejects a connection.
+pub(crate) async fn trace_lifecycle(pool: &str, conn: &mut MySqlConnection, event: &'static str) {
+    if !tracing::enabled!(tracing::Level::TRACE) {
+        return;
+    }
+    match connection_id(conn).await {
+        Ok(id) => trace!(pool, connection_id = id, event, "TiDB connection {}", event),
+        Err(err) => warn!(pool, event, "Failed to read the TiDB connection id: {}", err),
+    }
+}
+
+/// Returns the server-side id of the session behind `conn`.
+///
+/// This is the id shown in `SHOW PROCESSLIST` and accepted by `KILL`, which makes it possible to
+/// cancel a long-running statement from another connection.
+///
+/// ## Example:
+/// ```rust,ignore
+/// let mut conn = pool.acquire().await?;
+/// let id = connection_id(&mut conn).await?;
+/// ```
+pub async fn connection_id(conn: &mut MySqlConnection) -> Result<u64, Error> {
+    sqlx::query_scalar("SELECT CONNECTION_ID()").fetch_one(conn).await
+}
+
+/// Runs `f` on a pooled connection that reads data up to `staleness` old.
+///
+/// Sets TiDB's `tidb_read_staleness` on the connection for the duration of `f` and resets it
+/// afterwards. Reads inside `f` then use a snapshot at most `staleness` old (bounded
+/// staleness): TiDB picks the newest timestamp within that window that a nearby replica can
+/// serve, which takes load off the region leaders but may return data that is slightly out of
+/// date. Writes are not affected by the setting.
+///
+/// `staleness` is truncated to whole seconds and must be at least one second, otherwise
+/// [`Error::InvalidArgument`] is returned. If the setting cannot be reset, because the reset
+/// fails, `f` panics or the returned future is dropped before it completes, the connection is
+/// closed instead of being returned to the pool.
+///
+/// ## Example:
+/// ```rust,ignore
+/// let total: i64 = with_stale_read(&pool, Duration::from_secs(5), |conn| {
+///     Box::pin(async move {
+///         sqlx::query_scalar("SELECT COUNT(*) FROM orders").fetch_one(conn).await
+///     })
+/// })
+/// .await?;
+/// ```
+pub async fn with_stale_read<F, T>(
+    pool: &MySqlPool,
+    staleness: Duration,
+    f: F,
+) -> Result<T, Error>
+where
+    F: for<'c> FnOnce(&'c mut MySqlConnection) -> BoxFuture<'c, Result<T, Error>>,
+{
+    let seconds = staleness.as_secs();
+    if seconds == 0 {
+        return Err(Error::InvalidArgument(
+            "read staleness must be at least one second".into(),
+        ));
+    }
+
+    let mut guard = StaleReadGuard { conn: pool.acquire().await?, reset: false };
+    let set_staleness = format!("SET @@tidb_read_staleness = '-{}'", seconds);
+    guard.conn.execute(set_staleness.as_str()).await?;
+
+    let result = f(&mut guard.conn).await;
+
+    match guard.conn.execute("SET @@tidb_read_staleness = ''").await {
+        Ok(_) => guard.reset = true,
+        Err(err) => warn!("Failed to reset tidb_read_staleness ({}); closing the connection", err),
+    }
+    result
+}
+
+/// Closes the connection on drop unless `tidb_read_staleness` was reset, so a cancelled or
+/// panicking [`with_stale_read`] never returns a stale connection to the pool.
+struct StaleReadGuard {
+    conn: PoolConnection<MySql>,
+    reset: bool,
+}
+
+impl Drop for StaleReadGuard {
+    fn drop(&mut self) {
+        if !self.reset {
+            self.conn.close_on_drop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{build_pool_from_config, test_support::integration_config};
+
+    /// Test deserialization of the typed session flags.
+    #[test]
+    fn test_deserialize_session_flags() {
+        let toml_data = r#"
+        tidbEnablePaging = true
+        tidbEnableClusteredIndex = false
+        "#;
+
+        let flags: TiDBSessionFlags = toml::from_str(toml_data).expect("Failed to deserialize TOML");
+
+        assert_eq!(flags.tidb_enable_paging, Some(true));
+        assert_eq!(flags.tidb_enable_clustered_index, Some(false));
+    }
+
+    /// Test that set flags become `SET SESSION` statements for the hook.
+    #[test]
+    fn test_session_init_statements_from_flags() {
+        let mut config = integration_config();
+        assert!(session_init_statements(&config).is_empty());
+
+        config.session_flags.tidb_enable_paging = Some(true);
+        config.session_flags.tidb_enable_clustered_index = Some(false);
+
+        assert_eq!(
+            session_init_statements(&config),
+            vec![
+                "SET SESSION tidb_enable_paging = ON",
+                "SET SESSION tidb_enable_clustered_index = OFF",
+            ]
+        );
+    }
+
+    /// Test that `init_command` runs after the session flags and that blank commands are skipped.
+    #[test]
+    fn test_session_init_statements_with_init_command() {
+        let mut config = integration_config();
+        config.init_command = Some("  ".into());
+        assert!(session_init_statements(&config).is_empty());
+
+        config.session_flags.tidb_enable_paging = Some(true);
+        config.init_command = Some("SET @a = 1; SET @b = 2".into());
+
+        assert_eq!(
+            session_init_statements(&config),
+            vec!["SET SESSION tidb_enable_paging = ON", "SET @a = 1; SET @b = 2"]
+        );
+    }
+
+    /// Test that `group_concat_max_len` is set before the `init_command`.
+    #[test]
+    fn test_session_init_statements_with_group_concat_max_len() {
+        let mut config = integration_config();
+        config.group_concat_max_len = Some(1 << 20);
+        config.init_command = Some("SET @a = 1".into());
+
+        assert_eq!(
+            session_init_statements(&config),
+            vec!["SET SESSION group_concat_max_len = 1048576", "SET @a = 1"]
+        );
+    }
+
+    /// Test that `time_zone` registers a quoted `SET SESSION time_zone` before the
+    /// `init_command`.
+    #[test]
+    fn test_session_init_statements_with_time_zone() {
+        let mut config = integration_config();
+        config.time_zone = Some("+00:00".into());
+        config.init_command = Some("SET @a = 1".into());
+
+        assert_eq!(
+            session_init_statements(&config),
+            vec!["SET SESSION time_zone = '+00:00'", "SET @a = 1"]
+        );
+
+        config.time_zone = Some("x' OR '1".into());
+        assert_eq!(
+            session_init_statements(&config)[0],
+            "SET SESSION time_zone = 'x'' OR ''1'"
+        );
+    }
+
+    /// Test that a session profile only overrides the settings it has, and that its `sql_mode`
+    /// and time zone are set before the `init_command`.
+    #[test]
+    fn test_with_session_profile() {
+        let mut config = integration_config();
+        config.time_zone = Some("+02:00".into());
+        config.init_command = Some("SET @a = 1".into());
+
+        let config = config.with_session_profile(SessionProfile {
+            sql_mode: Some("ANSI_QUOTES".into()),
+            ..SessionProfile::default()
+        });
+        assert_eq!(config.time_zone.as_deref(), Some("+02:00"));
+        assert_eq!(config.charset, None);
+        assert_eq!(
+            session_init_statements(&config),
+            vec![
+                "SET SESSION time_zone = '+02:00', sql_mode = 'ANSI_QUOTES'",
+                "SET @a = 1"
+            ]
+        );
+
+        let profile = SessionProfile::mysql_compat();
+        let config = config.with_session_profile(profile.clone());
+        assert_eq!(config.charset, profile.charset);
+        assert_eq!(config.collation, profile.collation);
+        assert_eq!(config.sql_mode, profile.sql_mode);
+        assert_eq!(config.time_zone.as_deref(), Some("+00:00"));
+    }
+
+    /// Test that the `init_sql` statements run in order after the `init_command`, so the hook is
+    /// installed even when nothing else is configured.
+    #[test]
+    fn test_session_init_statements_with_init_sql() {
+        let mut config = integration_config();
+        config.pool_options.init_sql = vec!["SET @a = 1".into(), " ".into(), "SET @b = 2".into()];
+        assert_eq!(session_init_statements(&config), vec!["SET @a = 1", "SET @b = 2"]);
+
+        config.init_command = Some("SET @c = 3".into());
+        assert_eq!(
+            session_init_statements(&config),
+            vec!["SET @c = 3", "SET @a = 1", "SET @b = 2"]
+        );
+    }
+
+    /// Test that `init_sql` runs on new connections, and that a failing statement fails the
+    /// build.
+    #[tokio::test]
+    #[ignore = "requires a running TiDB instance"]
+    async fn test_init_sql_applied_on_connect() {
+        let mut config = integration_config();
+        config.pool_options.init_sql = vec![
+            "SET @tidb_pool_init = 41".into(),
+            "SET @tidb_pool_init = @tidb_pool_init + 1".into(),
+        ];
+        let pool = build_pool_from_config(config.clone())
+            .await
+            .expect("Failed to build pool");
+
+        let value: i64 = sqlx::query_scalar("SELECT @tidb_pool_init")
+            .fetch_one(&pool)
+            .await
+            .expect("Failed to read variable");
+        assert_eq!(value, 42);
+
+        config.pool_options.init_sql = vec!["SELECT * FROM tidb_pool_no_such_table".into()];
+        build_pool_from_config(config)
+            .await
+            .expect_err("Failing init_sql should fail the build");
+    }
+
+    /// Test that the session time zone is applied, and that an unknown zone fails the build.
+    #[tokio::test]
+    #[ignore = "requires a running TiDB instance"]
+    async fn test_time_zone_applied_on_connect() {
+        let mut config = integration_config();
+        config.time_zone = Some("+02:00".into());
+        let pool = build_pool_from_config(config.clone())
+            .await
+            .expect("Failed to build pool");
+
+        let time_zone: String = sqlx::query_scalar("SELECT @@session.time_zone")
+            .fetch_one(&pool)
+            .await
+            .expect("Failed to read time_zone");
+        assert_eq!(time_zone, "+02:00");
+
+        config.time_zone = Some("Mars/Olympus_Mons".into());
+        let err = build_pool_from_config(config)
+            .await
+            .expect_err("Unknown time zone should fail");
+        assert!(err.to_string().contains("time zone"), "{}", err);
+    }
+
+    /// Test that every setting of a session profile is applied to new connections.
+    #[tokio::test]
+    #[ignore = "requires a running TiDB instance"]
+    async fn test_session_profile_applied_on_connect() {
+        let profile = SessionProfile {
+            charset: Some("utf8mb4".into()),
+            collation: Some("utf8mb4_unicode_ci".into()),
+            sql_mode: Some("STRICT_TRANS_TABLES,ANSI_QUOTES".into()),
+            time_zone: Some("+03:00".into()),
+        };
+        let config = integration_config().with_session_profile(profile);
+        let pool = build_pool_from_config(config)
+            .await
+            .expect("Failed to build pool");
+
+        let (charset, collation, sql_mode, time_zone): (String, String, String, String) =
+            sqlx::query_as(
+                "SELECT @@session.character_set_connection, @@session.collation_connection, \
+                 @@session.sql_mode, @@session.time_zone",
+            )
+            .fetch_one(&pool)
+            .await
+            .expect("Failed to read session variables");
+        assert_eq!(charset, "utf8mb4");
+        assert_eq!(collation, "utf8mb4_unicode_ci");
+        assert_eq!(sql_mode, "STRICT_TRANS_TABLES,ANSI_QUOTES");
+        assert_eq!(time_zone, "+03:00");
+    }
+
+    /// Test that a long `GROUP_CONCAT` is not truncated once the limit is raised.
+    #[tokio::test]
+    #[ignore = "requires a running TiDB instance"]
+    async fn test_group_concat_max_len_applied_on_connect() {
+        let mut config = integration_config();
+        config.group_concat_max_len = Some(1 << 20);
+        let pool = build_pool_from_config(config).await.expect("Failed to build pool");
+
+        let len: i64 = sqlx::query_scalar(
+            "SELECT LENGTH(GROUP_CONCAT(REPEAT('x', 100))) FROM \
+             (SELECT 1 FROM information_schema.columns LIMIT 100) AS t",
+        )
+        .fetch_one(&pool)
+        .await
+        .expect("Failed to run GROUP_CONCAT");
+
+        // 100 values of 100 bytes plus 99 separators, well above the 1024-byte default.
+        assert_eq!(len, 100 * 100 + 99);
+    }
+
+    /// Test that a multi-statement `init_command` is fully applied on new connections.
+    #[tokio::test]
+    #[ignore = "requires a running TiDB instance"]
+    async fn test_init_command_applied_on_connect() {
+        let mut config = integration_config();
+        config.init_command = Some("SET @tidb_pool_a = 1; SET @tidb_pool_b = 2".into());
+        let pool = build_pool_from_config(config).await.expect("Failed to build pool");
+
+        let (a, b): (i64, i64) = sqlx::query_as("SELECT @tidb_pool_a, @tidb_pool_b")
+            .fetch_one(&pool)
+            .await
+            .expect("Failed to read user variables");
+
+        assert_eq!((a, b), (1, 2));
+    }
+
+    /// Test that the hook applies the flags on new connections.
+    #[tokio::test]
+    #[ignore = "requires a running TiDB instance"]
+    async fn test_session_flags_applied_on_connect() {
+        let mut config = integration_config();
+        config.session_flags.tidb_enable_paging = Some(false);
+        let pool = build_pool_from_config(config).await.expect("Failed to build pool");
+
+        let paging: String = sqlx::query_scalar("SELECT CAST(@@tidb_enable_paging AS CHAR)")
+            .fetch_one(&pool)
+            .await
+            .expect("Failed to read session variable");
+
+        assert_eq!(paging, "0");
+    }
+
+    /// Test that the connection id of a live session is positive.
+    #[tokio::test]
+    #[ignore = "requires a running TiDB instance"]
+    async fn test_connection_id() {
+        let pool = build_pool_from_config(integration_config())
+            .await
+            .expect("Failed to build pool");
+        let mut conn = pool.acquire().await.expect("Failed to acquire connection");
+
+        let id = connection_id(&mut conn).await.expect("Failed to read connection id");
+
+        assert!(id > 0);
+    }
+
+    /// Test that sub-second staleness is rejected before acquiring a connection.
+    #[tokio::test]
+    async fn test_with_stale_read_rejects_sub_second_staleness() {
+        let pool = MySqlPool::connect_lazy("mysql://root@127.0.0.1:1/test")
+            .expect("Failed to build lazy pool");
+
+        let result: Result<(), Error> =
+            with_stale_read(&pool, Duration::from_millis(500), |_conn| Box::pin(async { Ok(()) }))
+                .await;
+
+        assert!(matches!(result, Err(Error::InvalidArgument(_))));
+    }
+
+    /// Test that the staleness is applied inside the closure and reset afterwards.
+    #[tokio::test]
+    #[ignore = "requires a running TiDB instance"]
+    async fn test_with_stale_read() {
+        let mut config = integration_config();
+        config.pool_options.max_connections = 1;
+        let pool = build_pool_from_config(config).await.expect("Failed to build pool");
+
+        let result = with_stale_read(&pool, Duration::from_secs(5), |conn| {
+            Box::pin(async move {
+                sqlx::query_scalar::<_, String>("SELECT CAST(@@tidb_read_staleness AS CHAR)")
+                    .fetch_one(conn)
+                    .await
+            })
+        })
+        .await;
+        let staleness = match result {
+            Ok(staleness) => staleness,
+            // Servers without `tidb_read_staleness` cannot run this test
+            Err(Error::Database(err)) if err.message().contains("Unknown system variable") => {
+                return
+            }
+            Err(err) => panic!("stale read failed: {}", err),
+        };
+        assert_eq!(staleness, "-5");
+
+        let after: String = sqlx::query_scalar("SELECT CAST(@@tidb_read_staleness AS CHAR)")
+            .fetch_one(&pool)
+            .await
+            .expect("Failed to read session variable");
+        assert_eq!(after, "");
+    }
+
+    /// Test that a stale read cancelled inside the closure does not leak the staleness.
+    #[tokio::test]
+    #[ignore = "requires a running TiDB instance"]
+    async fn test_with_stale_read_cancelled() {
+        let mut config = integration_config();
+        config.pool_options.max_connections = 1;
+        let pool = build_pool_from_config(config).await.expect("Failed to build pool");
+
+        let stale_read = with_stale_read(&pool, Duration::from_secs(5), |conn| {
+            Box::pin(async move {
+                conn.execute("SELECT 1").await?;
+                tokio::time::sleep(Duration::from_secs(60)).await;
+                Ok(())
+            })
+        });
+        match tokio::time::timeout(Duration::from_secs(2), stale_read).await {
+            Err(_) => {}
+            // Servers without `tidb_read_staleness` cannot run this test
+            Ok(Err(Error::Database(err))) if err.message().contains("Unknown system variable") => {
+                return
+            }
+            Ok(result) => panic!("stale read was not cancelled: {:?}", result),
+        }
+
+        let after: String = sqlx::query_scalar("SELECT CAST(@@tidb_read_staleness AS CHAR)")
+            .fetch_one(&pool)
+            .await
+            .expect("Failed to read session variable");
+        assert_eq!(after, "");
+    }
+}
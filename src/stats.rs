@@ -0,0 +1,135 @@
+//! Point-in-time pool statistics for metrics and dashboards.
+
+use sqlx::MySqlPool;
+
+/// Snapshot of a pool's connection counts at a point in time.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct PoolStats {
+    /// Total number of connections currently held by the pool (idle and in use).
+    pub size: u32,
+    /// Number of idle connections.
+    pub num_idle: u32,
+}
+
+/// Change in [`PoolStats`] between two snapshots.
+///
+/// Positive values mean the count grew since the previous snapshot.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct PoolStatsDelta {
+    /// Change in the total number of connections.
+    pub size: i64,
+    /// Change in the number of idle connections.
+    pub num_idle: i64,
+    /// Change in the number of connections in use.
+    pub in_use: i64,
+}
+
+impl PoolStats {
+    /// Takes a snapshot of the pool's current connection counts.
+    pub fn from_pool(pool: &MySqlPool) -> Self {
+        PoolStats {
+            size: pool.size(),
+            num_idle: u32::try_from(pool.num_idle()).unwrap_or(u32::MAX),
+        }
+    }
+
+    /// Number of connections currently checked out of the pool.
+    pub fn in_use(&self) -> u32 {
+        self.size.saturating_sub(self.num_idle)
+    }
+
+    /// Fraction of `max` connections in use, clamped to `[0, 1]`.
+    ///
+    /// Returns `0.0` when `max` is zero.
+    pub fn utilization(&self, max: u32) -> f32 {
+        if max == 0 {
+            return 0.0;
+        }
+        (self.in_use() as f32 / max as f32).clamp(0.0, 1.0)
+    }
+
+    /// Computes the change from `previous` to this snapshot.
+    ///
+    /// ## Example:
+    /// ```rust,ignore
+    /// let previous = PoolStats::from_pool(&pool);
+    /// // ... one scrape interval later ...
+    /// let delta = PoolStats::from_pool(&pool).diff(&previous);
+    /// ```
+    pub fn diff(&self, previous: &PoolStats) -> PoolStatsDelta {
+        PoolStatsDelta {
+            size: i64::from(self.size) - i64::from(previous.size),
+            num_idle: i64::from(self.num_idle) - i64::from(previous.num_idle),
+            in_use: i64::from(self.in_use()) - i64::from(previous.in_use()),
+        }
+    }
+}
+
+/// Fraction of `max` connections of `pool` currently in use, clamped to `[0, 1]`.
+///
+/// Computed as `(size - num_idle) / max`, a ready-to-emit gauge for autoscaling. `max` is
+/// usually the pool's `max_connections`.
+///
+/// ## Example:
+/// ```rust,ignore
+/// let max = pool.options().get_max_connections();
+/// gauge!("tidb_pool_utilization").set(utilization(&pool, max));
+/// ```
+pub fn utilization(pool: &MySqlPool, max: u32) -> f32 {
+    PoolStats::from_pool(pool).utilization(max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test the delta when the pool grows.
+    #[test]
+    fn test_diff_increasing() {
+        let previous = PoolStats { size: 2, num_idle: 2 };
+        let current = PoolStats { size: 5, num_idle: 1 };
+
+        let delta = current.diff(&previous);
+
+        assert_eq!(delta, PoolStatsDelta { size: 3, num_idle: -1, in_use: 4 });
+    }
+
+    /// Test the delta when the pool shrinks.
+    #[test]
+    fn test_diff_decreasing() {
+        let previous = PoolStats { size: 10, num_idle: 3 };
+        let current = PoolStats { size: 4, num_idle: 4 };
+
+        let delta = current.diff(&previous);
+
+        assert_eq!(delta, PoolStatsDelta { size: -6, num_idle: 1, in_use: -7 });
+    }
+
+    /// Test utilization of an empty, a partially used and a fully used pool.
+    #[test]
+    fn test_utilization() {
+        assert_eq!(PoolStats { size: 0, num_idle: 0 }.utilization(10), 0.0);
+        assert_eq!(PoolStats { size: 4, num_idle: 4 }.utilization(10), 0.0);
+        assert_eq!(PoolStats { size: 6, num_idle: 1 }.utilization(10), 0.5);
+        assert_eq!(PoolStats { size: 10, num_idle: 0 }.utilization(10), 1.0);
+    }
+
+    /// Test that utilization stays within `[0, 1]` for inconsistent inputs.
+    #[test]
+    fn test_utilization_clamped() {
+        assert_eq!(PoolStats { size: 12, num_idle: 0 }.utilization(10), 1.0);
+        assert_eq!(PoolStats { size: 3, num_idle: 0 }.utilization(0), 0.0);
+    }
+
+    /// Test that the delta serializes with camelCase keys.
+    #[test]
+    fn test_serialize_delta() {
+        let delta = PoolStatsDelta { size: 1, num_idle: -2, in_use: 3 };
+
+        let json = serde_json::to_string(&delta).expect("Failed to serialize delta");
+
+        assert_eq!(json, r#"{"size":1,"numIdle":-2,"inUse":3}"#);
+    }
+}
@@ -1,2 +1,125 @@
+use std::fmt;
+
+use sqlx::Error;
+
 /// Type alias to improve readability.
-pub type TableFamilyName = &'static str;
\ No newline at end of file
+pub type TableFamilyName = &'static str;
+
+/// Maximum length of a MySQL/TiDB identifier.
+const MAX_IDENTIFIER_LEN: usize = 64;
+
+/// A validated table name, optionally qualified with a database (`db.table`).
+///
+/// Helpers that interpolate table names into SQL take a `TableName` so that only plain
+/// identifiers made of ASCII letters, digits, `_` and `$` can reach the query text.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TableName(String);
+
+impl TableName {
+    /// Validates `name` and wraps it as a `TableName`.
+    ///
+    /// Returns [`Error::InvalidArgument`] if any part of the name is not a valid identifier.
+    pub fn new(name: impl Into<String>) -> Result<Self, Error> {
+        let name = name.into();
+        let parts: Vec<&str> = name.split('.').collect();
+        if parts.len() > 2 {
+            return Err(Error::InvalidArgument(format!(
+                "invalid table name `{}`: expected `table` or `database.table`",
+                name
+            )));
+        }
+        for part in parts {
+            validate_identifier(part)?;
+        }
+        Ok(TableName(name))
+    }
+
+    /// Returns the table name as written.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Returns the database part of a qualified name, or `None` for a plain `table`.
+    pub fn database(&self) -> Option<&str> {
+        self.0.split_once('.').map(|(database, _)| database)
+    }
+
+    /// Returns the table part of the name, without any database qualifier.
+    pub fn table(&self) -> &str {
+        self.0.split_once('.').map_or(&self.0, |(_, table)| table)
+    }
+
+    /// Returns the table name with each part quoted in backticks, ready for use in SQL.
+    pub(crate) fn quoted(&self) -> String {
+        self.0
+            .split('.')
+            .map(quote_identifier)
+            .collect::<Vec<_>>()
+            .join(".")
+    }
+}
+
+impl fmt::Display for TableName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Checks that `ident` is a plain identifier safe to interpolate into SQL.
+pub(crate) fn validate_identifier(ident: &str) -> Result<(), Error> {
+    let valid = !ident.is_empty()
+        && ident.len() <= MAX_IDENTIFIER_LEN
+        && ident
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+    if valid {
+        Ok(())
+    } else {
+        Err(Error::InvalidArgument(format!(
+            "invalid identifier `{}`",
+            ident
+        )))
+    }
+}
+
+/// Quotes an already validated identifier in backticks.
+pub(crate) fn quote_identifier(ident: &str) -> String {
+    format!("`{}`", ident)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test that plain and database-qualified names are accepted and quoted.
+    #[test]
+    fn test_table_name_valid() {
+        assert_eq!(TableName::new("users").unwrap().quoted(), "`users`");
+        assert_eq!(
+            TableName::new("app.users_2024").unwrap().quoted(),
+            "`app`.`users_2024`"
+        );
+    }
+
+    /// Test splitting a name into its database and table parts.
+    #[test]
+    fn test_table_name_parts() {
+        let plain = TableName::new("users").unwrap();
+        assert_eq!((plain.database(), plain.table()), (None, "users"));
+
+        let qualified = TableName::new("app.users").unwrap();
+        assert_eq!((qualified.database(), qualified.table()), (Some("app"), "users"));
+    }
+
+    /// Test that names which could inject SQL are rejected.
+    #[test]
+    fn test_table_name_invalid() {
+        for name in ["", "users; DROP TABLE x", "a.b.c", "user`s", "users--"] {
+            assert!(
+                matches!(TableName::new(name), Err(Error::InvalidArgument(_))),
+                "`{}` should be rejected",
+                name
+            );
+        }
+    }
+}
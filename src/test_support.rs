@@ -0,0 +1,142 @@
+//! Helpers shared by the crate's unit and integration tests.
+//!
+//! Integration tests are marked `#[ignore]` and expect a reachable TiDB instance. Run them with
+//! `cargo test -- --ignored`, configuring the target through the `TIDB_TEST_HOST`,
+//! `TIDB_TEST_PORT`, `TIDB_TEST_USERNAME`, `TIDB_TEST_PASSWORD` and `TIDB_TEST_DATABASE`
+//! environment variables.
+
+use std::{
+    env,
+    fmt::Debug,
+    io,
+    sync::{Arc, Mutex},
+};
+
+use tracing::subscriber::DefaultGuard;
+
+use crate::config::{PoolOptions, TiDBConfig};
+
+/// Builds a configuration pointing at the TiDB instance used for integration tests.
+pub(crate) fn integration_config() -> TiDBConfig {
+    TiDBConfig {
+        host: env::var("TIDB_TEST_HOST").unwrap_or_else(|_| "127.0.0.1".into()),
+        port: env::var("TIDB_TEST_PORT").ok().and_then(|port| port.parse().ok()),
+        socket: None,
+        username: env::var("TIDB_TEST_USERNAME").unwrap_or_else(|_| "root".into()),
+        password: env::var("TIDB_TEST_PASSWORD").unwrap_or_default(),
+        database_name: env::var("TIDB_TEST_DATABASE").unwrap_or_else(|_| "test".into()),
+        pool_options: PoolOptions {
+            is_lazy: false,
+            ..PoolOptions::default()
+        },
+        ssl_ca: None,
+        ssl_mode: None,
+        ssl_client_cert: None,
+        ssl_client_key: None,
+        address_family: None,
+        session_flags: Default::default(),
+        min_server_version: None,
+        validate_on_build: false,
+        require_ssl: false,
+        allow_public_key_retrieval: None,
+        min_tls_version: None,
+        auth_plugin: None,
+        comparison_safe: false,
+        charset: None,
+        collation: None,
+        init_command: None,
+        group_concat_max_len: None,
+        time_zone: None,
+        sql_mode: None,
+    }
+}
+
+/// Extracts the value of `field` from the `Debug` output of `value`.
+///
+/// Used to inspect sqlx option structs whose fields have no public getter.
+pub(crate) fn debug_field(value: &impl Debug, field: &str) -> String {
+    let debug = format!("{:?}", value);
+    let marker = format!("{}: ", field);
+    let start = debug
+        .find(&marker)
+        .unwrap_or_else(|| panic!("`{}` not found in {}", field, debug))
+        + marker.len();
+    debug[start..]
+        .split([',', ' ', '}'])
+        .next()
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Log output captured by [`capture_logs`].
+#[derive(Clone, Default)]
+pub(crate) struct CapturedLogs(Arc<Mutex<Vec<u8>>>);
+
+impl CapturedLogs {
+    /// Returns everything logged so far.
+    pub(crate) fn contents(&self) -> String {
+        String::from_utf8_lossy(&self.0.lock().unwrap()).into_owned()
+    }
+}
+
+impl io::Write for CapturedLogs {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Captures events of every level, down to `TRACE`, emitted on the current thread until the
+/// guard is dropped.
+///
+/// Events are formatted with `tracing_subscriber::fmt`, so structured fields appear as
+/// `name=value`.
+pub(crate) fn capture_logs() -> (DefaultGuard, CapturedLogs) {
+    let logs = CapturedLogs::default();
+    let writer = logs.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::TRACE)
+        .with_ansi(false)
+        .with_writer(move || writer.clone())
+        .finish();
+    (tracing::subscriber::set_default(subscriber), logs)
+}
+
+/// A temp file deleted when dropped.
+pub(crate) struct TempFile(String);
+
+impl TempFile {
+    /// The path of the file.
+    pub(crate) fn path(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+/// Writes `content` to a temp file named after `file_name` and the process id, deleted when the
+/// returned guard is dropped.
+pub(crate) fn temp_file(file_name: &str, content: &str) -> TempFile {
+    let path = env::temp_dir().join(format!("tidb_pool_{}_{}", std::process::id(), file_name));
+    std::fs::write(&path, content).expect("Failed to write temp file");
+    TempFile(path.to_string_lossy().into_owned())
+}
+
+/// Writes a placeholder CA certificate to a uniquely named temp file, deleted when the returned
+/// guard is dropped.
+///
+/// Only the existence of the file is checked by validation; it is never parsed.
+pub(crate) fn temp_ca_file(name: &str) -> TempFile {
+    temp_file(
+        &format!("{}_ca.pem", name),
+        "-----BEGIN CERTIFICATE-----\n-----END CERTIFICATE-----\n",
+    )
+}
@@ -0,0 +1,43 @@
+//! Helpers running closures inside a database transaction.
+
+use futures_util::future::BoxFuture;
+use sqlx::{Error, MySql, MySqlPool, Transaction};
+
+/// Runs `f` inside a transaction, committing if it succeeds and rolling back if it fails.
+///
+/// The error returned by `f` is passed through unchanged; a failure to roll back is only logged,
+/// since the server discards the transaction anyway once the connection is closed or reused.
+///
+/// ## Example:
+/// ```rust,ignore
+/// with_transaction(&pool, |tx| {
+///     Box::pin(async move {
+///         sqlx::query("UPDATE accounts SET balance = balance - 10 WHERE id = 1")
+///             .execute(&mut **tx)
+///             .await?;
+///         sqlx::query("UPDATE accounts SET balance = balance + 10 WHERE id = 2")
+///             .execute(&mut **tx)
+///             .await?;
+///         Ok(())
+///     })
+/// })
+/// .await?;
+/// ```
+pub async fn with_transaction<F, T>(pool: &MySqlPool, f: F) -> Result<T, Error>
+where
+    F: for<'c> FnOnce(&'c mut Transaction<'static, MySql>) -> BoxFuture<'c, Result<T, Error>>,
+{
+    let mut tx = pool.begin().await?;
+    match f(&mut tx).await {
+        Ok(value) => {
+            tx.commit().await?;
+            Ok(value)
+        }
+        Err(err) => {
+            if let Err(rollback_err) = tx.rollback().await {
+                warn!("Failed to roll back TiDB transaction: {}", rollback_err);
+            }
+            Err(err)
+        }
+    }
+}
@@ -0,0 +1,330 @@
+//! Connecting to TiDB through an SSH bastion (requires the `tunnel` feature).
+//!
+//! The tunnel is a local port forward established with the system OpenSSH client (`ssh` must be
+//! on the `PATH`), so no additional Rust dependencies are pulled in. Authentication is
+//! non-interactive: use an SSH agent or an `identityFile`.
+
+use std::{
+    collections::VecDeque,
+    io,
+    net::{Ipv4Addr, SocketAddr, TcpListener},
+    ops::Deref,
+    process::Stdio,
+    time::Duration,
+};
+
+use sqlx::{Error, MySqlPool};
+use tokio::{
+    io::{AsyncBufReadExt, BufReader},
+    net::TcpStream,
+    process::{Child, ChildStderr, Command},
+    task::JoinHandle,
+};
+
+use crate::{build_pool_from_config, config::TiDBConfig};
+
+/// How long to wait for the local end of the tunnel to accept connections.
+const TUNNEL_READY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Interval between readiness probes of the local end of the tunnel.
+const TUNNEL_READY_POLL: Duration = Duration::from_millis(100);
+
+/// Number of automatically picked local ports tried before giving up.
+const TUNNEL_OPEN_ATTEMPTS: usize = 3;
+
+/// Number of trailing `ssh` stderr lines kept for error messages.
+const STDERR_TAIL_LINES: usize = 20;
+
+/// Settings for the SSH bastion used to reach TiDB.
+///
+/// # Example (TOML)
+/// ```toml
+/// bastionHost = "bastion.example.com"
+/// bastionUser = "deploy"
+/// identityFile = "~/.ssh/id_ed25519"
+/// ```
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SshTunnelConfig {
+    /// Hostname or IP address of the SSH bastion.
+    pub bastion_host: String,
+
+    /// SSH port of the bastion.
+    ///
+    /// If not specified, it defaults to 22.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bastion_port: Option<u16>,
+
+    /// User to log into the bastion as.
+    pub bastion_user: String,
+
+    /// Path to the private key used to authenticate with the bastion.
+    ///
+    /// Optional: If not specified, the SSH agent and default keys are used.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub identity_file: Option<String>,
+
+    /// Local port to listen on.
+    ///
+    /// Optional: If not specified, a free port is picked automatically.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub local_port: Option<u16>,
+}
+
+/// A running SSH local port forward. The `ssh` process is terminated when this is dropped.
+#[derive(Debug)]
+pub struct SshTunnel {
+    child: Child,
+    local_addr: SocketAddr,
+    stderr: JoinHandle<String>,
+}
+
+impl SshTunnel {
+    /// Starts forwarding a local port to `remote_host:remote_port` through the bastion and
+    /// waits until the local end accepts connections.
+    ///
+    /// When no `local_port` is configured, a free port is picked; if another process takes it
+    /// before `ssh` binds it, the tunnel is opened again on another port, up to three times.
+    pub async fn open(
+        tunnel: &SshTunnelConfig,
+        remote_host: &str,
+        remote_port: u16,
+    ) -> Result<Self, io::Error> {
+        let mut attempt = 1;
+        loop {
+            let local_port = match tunnel.local_port {
+                Some(port) => port,
+                None => TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?.local_addr()?.port(),
+            };
+            let result = SshTunnel::open_on(tunnel, local_port, remote_host, remote_port).await;
+            match result {
+                Err(err)
+                    if tunnel.local_port.is_none()
+                        && attempt < TUNNEL_OPEN_ATTEMPTS
+                        && is_bind_failure(&err) =>
+                {
+                    debug!(
+                        "SSH tunnel could not bind local port {}, retrying: {}",
+                        local_port, err
+                    );
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Starts `ssh` forwarding `local_port` and waits until the forward is ready.
+    async fn open_on(
+        tunnel: &SshTunnelConfig,
+        local_port: u16,
+        remote_host: &str,
+        remote_port: u16,
+    ) -> Result<Self, io::Error> {
+        let local_addr = SocketAddr::from((Ipv4Addr::LOCALHOST, local_port));
+
+        let mut command = Command::new("ssh");
+        command
+            .arg("-N")
+            .args(["-o", "ExitOnForwardFailure=yes", "-o", "BatchMode=yes"])
+            .arg("-L")
+            .arg(format!("{}:{}:{}", local_addr, remote_host, remote_port))
+            .arg("-p")
+            .arg(tunnel.bastion_port.unwrap_or(22).to_string());
+        if let Some(identity_file) = &tunnel.identity_file {
+            command.arg("-i").arg(identity_file);
+        }
+        command
+            .arg(format!("{}@{}", tunnel.bastion_user, tunnel.bastion_host))
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+
+        info!(
+            "Opening SSH tunnel {} -> {}:{} via {}",
+            local_addr, remote_host, remote_port, tunnel.bastion_host
+        );
+        let mut child = command.spawn()?;
+        let stderr = drain_stderr(child.stderr.take());
+        let mut tunnel = SshTunnel {
+            child,
+            local_addr,
+            stderr,
+        };
+        tunnel.wait_until_ready().await?;
+
+        Ok(tunnel)
+    }
+
+    /// Local address that forwards to the remote TiDB server.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    async fn wait_until_ready(&mut self) -> Result<(), io::Error> {
+        let attempts = TUNNEL_READY_TIMEOUT.as_millis() / TUNNEL_READY_POLL.as_millis();
+        for _ in 0..attempts {
+            if let Some(status) = self.child.try_wait()? {
+                // The pipe is closed once ssh has exited, so the drain task finishes
+                let stderr = (&mut self.stderr).await.unwrap_or_default();
+                return Err(io::Error::other(format!(
+                    "ssh exited with {}: {}",
+                    status,
+                    stderr.trim()
+                )));
+            }
+            let probe = TcpStream::connect(self.local_addr);
+            if let Ok(Ok(_)) = tokio::time::timeout(TUNNEL_READY_POLL, probe).await {
+                return Ok(());
+            }
+            tokio::time::sleep(TUNNEL_READY_POLL).await;
+        }
+
+        Err(io::Error::new(
+            io::ErrorKind::TimedOut,
+            format!("SSH tunnel on {} did not become ready", self.local_addr),
+        ))
+    }
+}
+
+impl Drop for SshTunnel {
+    fn drop(&mut self) {
+        debug!("Closing SSH tunnel on {}", self.local_addr);
+        // The process is reaped in the background by Tokio
+        let _ = self.child.start_kill();
+        self.stderr.abort();
+    }
+}
+
+/// Reads `stderr` until `ssh` exits, so a verbose `ssh` never blocks on a full pipe.
+///
+/// Lines are logged at `debug` level, and the last ones are returned for error messages.
+fn drain_stderr(stderr: Option<ChildStderr>) -> JoinHandle<String> {
+    tokio::spawn(async move {
+        let mut tail = VecDeque::with_capacity(STDERR_TAIL_LINES);
+        if let Some(stderr) = stderr {
+            let mut lines = BufReader::new(stderr).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                debug!("ssh: {}", line);
+                if tail.len() == STDERR_TAIL_LINES {
+                    tail.pop_front();
+                }
+                tail.push_back(line);
+            }
+        }
+        Vec::from(tail).join("\n")
+    })
+}
+
+/// Returns `true` if `ssh` failed because the local end of the forward could not be bound.
+fn is_bind_failure(err: &io::Error) -> bool {
+    let message = err.to_string();
+    ["Address already in use", "cannot listen to port", "Could not request local forwarding"]
+        .iter()
+        .any(|pattern| message.contains(pattern))
+}
+
+/// A pool whose connections go through an [`SshTunnel`].
+///
+/// Dereferences to the underlying [`MySqlPool`]. The tunnel is torn down when this value is
+/// dropped, so keep it alive for as long as the pool (or any clone of it) is in use.
+#[derive(Debug)]
+pub struct TunneledPool {
+    pool: MySqlPool,
+    tunnel: SshTunnel,
+}
+
+impl TunneledPool {
+    /// Local address of the tunnel the pool connects through.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.tunnel.local_addr()
+    }
+}
+
+impl Deref for TunneledPool {
+    type Target = MySqlPool;
+    fn deref(&self) -> &Self::Target {
+        &self.pool
+    }
+}
+
+/// Builds a connection pool to TiDB through an SSH bastion.
+///
+/// Opens a local port forward to `config.host:config.port` via the bastion described by
+/// `tunnel`, then builds the pool with [`build_pool_from_config`] pointed at the local end of
+/// the forward.
+///
+/// ## Example:
+/// ```rust,ignore
+/// let pool = build_pool_via_tunnel(config, &tunnel_config).await?;
+/// sqlx::query("SELECT 1").execute(&*pool).await?;
+/// ```
+pub async fn build_pool_via_tunnel(
+    config: TiDBConfig,
+    tunnel: &SshTunnelConfig,
+) -> Result<TunneledPool, Error> {
+    let tunnel = SshTunnel::open(tunnel, &config.host, config.port.unwrap_or(4000)).await?;
+    let pool = build_pool_from_config(tunneled_config(config, tunnel.local_addr())).await?;
+    Ok(TunneledPool { pool, tunnel })
+}
+
+/// Rewrites `config` so that it connects to the local end of a tunnel.
+fn tunneled_config(mut config: TiDBConfig, local_addr: SocketAddr) -> TiDBConfig {
+    config.host = local_addr.ip().to_string();
+    config.port = Some(local_addr.port());
+    config.address_family = None;
+    config
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::integration_config;
+
+    /// Test that only local bind failures of `ssh` are retried on another port.
+    #[test]
+    fn test_is_bind_failure() {
+        let err = io::Error::other(
+            "ssh exited with exit status: 255: bind [127.0.0.1]:14000: Address already in use\n\
+             channel_setup_fwd_listener_tcpip: cannot listen to port: 14000",
+        );
+        assert!(is_bind_failure(&err));
+
+        let err = io::Error::other("ssh exited with exit status: 255: Permission denied");
+        assert!(!is_bind_failure(&err));
+    }
+
+    /// Test that stderr is drained while the process runs and its last lines are kept.
+    #[tokio::test]
+    async fn test_drain_stderr_keeps_tail() {
+        let mut child = Command::new("sh")
+            .args(["-c", "i=0; while [ $i -lt 5000 ]; do echo line $i >&2; i=$((i+1)); done"])
+            .stderr(Stdio::piped())
+            .spawn()
+            .expect("Failed to spawn sh");
+        let stderr = drain_stderr(child.stderr.take());
+
+        child.wait().await.expect("sh did not exit");
+        let tail = stderr.await.unwrap();
+        assert_eq!(tail.lines().count(), STDERR_TAIL_LINES);
+        assert_eq!(tail.lines().last(), Some("line 4999"));
+    }
+
+    /// Test that a pool built for a tunnel targets the local forward address.
+    #[tokio::test]
+    async fn test_tunneled_pool_targets_local_forward() {
+        let local_addr = SocketAddr::from((Ipv4Addr::LOCALHOST, 14000));
+        let mut config = integration_config();
+        config.host = "tidb.internal".into();
+        config.pool_options.is_lazy = true;
+
+        let pool = build_pool_from_config(tunneled_config(config, local_addr))
+            .await
+            .expect("Failed to build lazy pool");
+
+        let options = pool.connect_options();
+        assert_eq!(options.get_host(), "127.0.0.1");
+        assert_eq!(options.get_port(), 14000);
+    }
+}
@@ -0,0 +1,182 @@
+//! Server version parsing and comparison.
+
+use std::{cmp::Ordering, fmt};
+
+use sqlx::{Error, MySqlPool};
+
+/// A server version, as reported by `SELECT VERSION()`.
+///
+/// TiDB reports a MySQL-compatible prefix followed by the TiDB release, e.g.
+/// `5.7.25-TiDB-v7.5.0`, while MySQL reports its own release, e.g. `8.0.33-log`. Versions are
+/// ordered by their [`effective`][Self::effective] release. A TiDB and a MySQL server with the
+/// same release number are not comparable.
+///
+/// ## Example:
+/// ```rust
+/// use tidb_pool::ServerVersion;
+///
+/// let version = ServerVersion::parse("5.7.25-TiDB-v7.5.0").unwrap();
+/// assert!(version.is_tidb());
+/// assert_eq!(version.tidb_version(), Some((7, 5, 0)));
+/// assert!(version > ServerVersion::parse("5.7.25-TiDB-v6.5.3").unwrap());
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ServerVersion {
+    raw: String,
+    mysql: (u64, u64, u64),
+    tidb: Option<(u64, u64, u64)>,
+}
+
+impl ServerVersion {
+    /// Parses a `VERSION()` string, returning `None` if it does not start with a version number.
+    pub fn parse(version: &str) -> Option<ServerVersion> {
+        let version = version.trim();
+        let (mysql, tidb) = match version.split_once("-TiDB-v") {
+            Some((mysql, tidb)) => (parse_version(mysql)?, Some(parse_version(tidb)?)),
+            None => (parse_version(version)?, None),
+        };
+        Some(ServerVersion {
+            raw: version.to_owned(),
+            mysql,
+            tidb,
+        })
+    }
+
+    /// Runs `SELECT VERSION()` on `pool` and parses the result.
+    ///
+    /// Fails with [`Error::Protocol`] if the server reports a version that cannot be parsed.
+    pub async fn fetch(pool: &MySqlPool) -> Result<ServerVersion, Error> {
+        let version: String = sqlx::query_scalar("SELECT VERSION()").fetch_one(pool).await?;
+        ServerVersion::parse(&version)
+            .ok_or_else(|| Error::Protocol(format!("unrecognized server version `{}`", version)))
+    }
+
+    /// Returns `true` if the server is TiDB.
+    pub fn is_tidb(&self) -> bool {
+        self.tidb.is_some()
+    }
+
+    /// The TiDB release, e.g. `(7, 5, 0)` for `5.7.25-TiDB-v7.5.0`, or `None` for MySQL.
+    pub fn tidb_version(&self) -> Option<(u64, u64, u64)> {
+        self.tidb
+    }
+
+    /// The MySQL version, or the MySQL-compatible prefix reported by TiDB.
+    pub fn mysql_version(&self) -> (u64, u64, u64) {
+        self.mysql
+    }
+
+    /// The release that matters for feature checks: the TiDB release for TiDB, and the MySQL
+    /// version otherwise.
+    pub fn effective(&self) -> (u64, u64, u64) {
+        self.tidb.unwrap_or(self.mysql)
+    }
+
+    /// Returns `true` if the [`effective`][Self::effective] release is at least `required`,
+    /// e.g. `"v7.1"` or `"7.1.0"`. Returns `false` if `required` cannot be parsed.
+    pub fn at_least(&self, required: &str) -> bool {
+        parse_version(required).is_some_and(|required| self.effective() >= required)
+    }
+
+    /// The version string as reported by the server.
+    pub fn as_str(&self) -> &str {
+        &self.raw
+    }
+}
+
+impl PartialOrd for ServerVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if self.is_tidb() != other.is_tidb() {
+            return None;
+        }
+        match self.effective().cmp(&other.effective()) {
+            // Same release with different build suffixes, e.g. `8.0.33` and `8.0.33-log`
+            Ordering::Equal if self != other => None,
+            ordering => Some(ordering),
+        }
+    }
+}
+
+impl fmt::Display for ServerVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.raw)
+    }
+}
+
+/// Parses the leading `major[.minor[.patch]]` of a version string.
+pub(crate) fn parse_version(version: &str) -> Option<(u64, u64, u64)> {
+    let version = version.trim().trim_start_matches('v');
+    let numeric = version
+        .split(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .next()?;
+    let mut parts = numeric.split('.').filter(|part| !part.is_empty());
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next().map_or(Some(0), |part| part.parse().ok())?;
+    let patch = parts.next().map_or(Some(0), |part| part.parse().ok())?;
+    Some((major, minor, patch))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{build_pool_from_config, test_support::integration_config};
+
+    /// Test parsing TiDB and MySQL version strings.
+    #[test]
+    fn test_parse_server_version() {
+        let cases = [
+            ("5.7.25-TiDB-v7.5.0", (5, 7, 25), Some((7, 5, 0))),
+            ("8.0.11-TiDB-v8.1.1-serverless", (8, 0, 11), Some((8, 1, 1))),
+            ("5.7.25-TiDB-v6.5.3-20230619", (5, 7, 25), Some((6, 5, 3))),
+            ("8.0.11-TiDB-v7.1.0-alpha", (8, 0, 11), Some((7, 1, 0))),
+            ("8.0.33-log", (8, 0, 33), None),
+            ("8.0.36-0ubuntu0.22.04.1", (8, 0, 36), None),
+            ("5.7.44", (5, 7, 44), None),
+            ("10.11.6-MariaDB-0+deb12u1", (10, 11, 6), None),
+        ];
+
+        for (raw, mysql, tidb) in cases {
+            let version = ServerVersion::parse(raw).expect(raw);
+            assert_eq!(version.as_str(), raw);
+            assert_eq!(version.mysql_version(), mysql, "{}", raw);
+            assert_eq!(version.tidb_version(), tidb, "{}", raw);
+            assert_eq!(version.is_tidb(), tidb.is_some(), "{}", raw);
+            assert_eq!(version.effective(), tidb.unwrap_or(mysql), "{}", raw);
+        }
+
+        assert_eq!(ServerVersion::parse("garbage"), None);
+        assert_eq!(ServerVersion::parse("5.7.25-TiDB-vnext"), None);
+        assert_eq!(parse_version("v7.1"), Some((7, 1, 0)));
+    }
+
+    /// Test ordering by the effective release and minimum version checks.
+    #[test]
+    fn test_server_version_ordering() {
+        let parse = |raw| ServerVersion::parse(raw).unwrap();
+
+        assert!(parse("5.7.25-TiDB-v7.5.0") > parse("8.0.11-TiDB-v6.5.3"));
+        assert!(parse("8.0.33") < parse("8.0.34-log"));
+        assert_eq!(parse("8.0.33").partial_cmp(&parse("8.0.33")), Some(Ordering::Equal));
+        assert_eq!(parse("8.0.33").partial_cmp(&parse("8.0.33-log")), None);
+        assert_eq!(parse("8.0.11-TiDB-v8.0.0").partial_cmp(&parse("8.0.0")), None);
+
+        let version = parse("5.7.25-TiDB-v7.5.0");
+        assert!(version.at_least("v7.1"));
+        assert!(version.at_least("7.5.0"));
+        assert!(!version.at_least("8"));
+        assert!(!version.at_least("garbage"));
+    }
+
+    /// Test fetching the version of a running server.
+    #[tokio::test]
+    #[ignore = "requires a running TiDB instance"]
+    async fn test_fetch_server_version() {
+        let pool = build_pool_from_config(integration_config())
+            .await
+            .expect("Failed to build pool");
+
+        let version = ServerVersion::fetch(&pool).await.expect("Failed to fetch version");
+
+        assert!(version.is_tidb(), "{}", version);
+    }
+}
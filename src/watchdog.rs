@@ -0,0 +1,234 @@
+//! Background task killing statements that run longer than a pool-wide limit.
+
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex, Weak},
+    time::Duration,
+};
+
+use sqlx::{mysql::MySqlConnectOptions, Connection, Error, Executor, MySqlConnection};
+use tokio::task::JoinHandle;
+
+use crate::{config::TiDBConfig, version::ServerVersion};
+
+/// Server-side ids of the connections opened by a pool.
+///
+/// The pool's `after_connect` hook holds the only strong reference, so the set is dropped
+/// together with the pool.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ConnectionIds(Arc<Mutex<HashSet<u64>>>);
+
+impl ConnectionIds {
+    /// Records the id of a newly opened connection.
+    pub(crate) fn insert(&self, id: u64) {
+        self.0.lock().unwrap_or_else(|err| err.into_inner()).insert(id);
+    }
+}
+
+/// The query watchdog of a pool, started with [`spawn`][Self::spawn] once the pool is built.
+#[derive(Debug)]
+pub(crate) struct QueryWatchdog {
+    ids: ConnectionIds,
+    limit_secs: u64,
+}
+
+impl QueryWatchdog {
+    /// Returns the watchdog for `config`, or `None` if `query_watchdog_secs` is not set.
+    pub(crate) fn from_config(config: &TiDBConfig) -> Option<QueryWatchdog> {
+        config.pool_options.query_watchdog_secs.map(|limit_secs| QueryWatchdog {
+            ids: ConnectionIds::default(),
+            limit_secs,
+        })
+    }
+
+    /// The set the pool's `after_connect` hook records its connection ids in.
+    pub(crate) fn connection_ids(&self) -> &ConnectionIds {
+        &self.ids
+    }
+
+    /// Spawns the watchdog task.
+    ///
+    /// The task sweeps the process list every half limit (at least once per second) and stops
+    /// once the pool has been dropped. It connects with `conn_options` on a connection of its
+    /// own, outside the pool, so it can still kill statements when they hold every pooled
+    /// connection.
+    pub(crate) fn spawn(self, conn_options: MySqlConnectOptions) -> JoinHandle<()> {
+        let QueryWatchdog { ids, limit_secs } = self;
+        let ids = Arc::downgrade(&ids.0);
+
+        tokio::spawn(async move {
+            let mut ticks = tokio::time::interval(sweep_period(limit_secs));
+            ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            let mut conn = None;
+            loop {
+                ticks.tick().await;
+                let Some(pool_ids) = live_ids(&ids) else {
+                    break;
+                };
+                if pool_ids.is_empty() {
+                    continue;
+                }
+                if let Err(err) =
+                    kill_overdue_queries(&mut conn, &conn_options, &ids, &pool_ids, limit_secs)
+                        .await
+                {
+                    warn!("TiDB query watchdog sweep failed: {}", err);
+                    // Reconnect on the next sweep
+                    conn = None;
+                }
+            }
+            debug!("TiDB query watchdog stopped");
+        })
+    }
+}
+
+/// Returns the ids recorded by the pool, or `None` once the pool has been dropped.
+fn live_ids(ids: &Weak<Mutex<HashSet<u64>>>) -> Option<Vec<u64>> {
+    let ids = ids.upgrade()?;
+    let ids = ids.lock().unwrap_or_else(|err| err.into_inner());
+    Some(ids.iter().copied().collect())
+}
+
+/// Returns the interval between two watchdog sweeps for a limit of `limit_secs`.
+fn sweep_period(limit_secs: u64) -> Duration {
+    Duration::from_millis(limit_secs.saturating_mul(500)).max(Duration::from_secs(1))
+}
+
+/// The watchdog's own connection, along with the process list table it sweeps.
+struct SweepConnection {
+    conn: MySqlConnection,
+    processlist: &'static str,
+}
+
+impl SweepConnection {
+    /// Connects with `conn_options`.
+    ///
+    /// On TiDB, `information_schema.processlist` only covers the instance serving the
+    /// connection, so the cluster-wide `cluster_processlist` is swept instead.
+    async fn connect(conn_options: &MySqlConnectOptions) -> Result<SweepConnection, Error> {
+        let mut conn = MySqlConnection::connect_with(conn_options).await?;
+        let version: String = sqlx::query_scalar("SELECT VERSION()").fetch_one(&mut conn).await?;
+        let processlist = if ServerVersion::parse(&version).is_some_and(|v| v.is_tidb()) {
+            "information_schema.cluster_processlist"
+        } else {
+            "information_schema.processlist"
+        };
+        Ok(SweepConnection { conn, processlist })
+    }
+}
+
+/// Builds the query listing the connections in `pool_ids` from `processlist`.
+fn processlist_query(processlist: &str, pool_ids: &[u64]) -> String {
+    let ids: Vec<String> = pool_ids.iter().map(u64::to_string).collect();
+    format!(
+        "SELECT CAST(ID AS UNSIGNED), COMMAND, CAST(TIME AS SIGNED) \
+         FROM {} WHERE ID IN ({})",
+        processlist,
+        ids.join(", ")
+    )
+}
+
+/// Issues `KILL QUERY` for every statement on one of the pool's connections that has been
+/// running for at least `limit_secs` seconds.
+///
+/// Runs on `conn`, opening it first if needed. Ids of connections that are no longer in the
+/// process list are forgotten.
+async fn kill_overdue_queries(
+    conn: &mut Option<SweepConnection>,
+    conn_options: &MySqlConnectOptions,
+    ids: &Weak<Mutex<HashSet<u64>>>,
+    pool_ids: &[u64],
+    limit_secs: u64,
+) -> Result<(), Error> {
+    let SweepConnection { conn, processlist } = match conn {
+        Some(conn) => conn,
+        None => conn.insert(SweepConnection::connect(conn_options).await?),
+    };
+
+    let processes: Vec<(u64, String, i64)> =
+        sqlx::query_as(&processlist_query(processlist, pool_ids))
+            .fetch_all(&mut *conn)
+            .await?;
+
+    if let Some(ids) = ids.upgrade() {
+        let alive: HashSet<u64> = processes.iter().map(|(id, _, _)| *id).collect();
+        ids.lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .retain(|id| alive.contains(id));
+    }
+
+    let overdue = processes.into_iter().filter(|(_, command, time)| {
+        command == "Query" && u64::try_from(*time).is_ok_and(|time| time >= limit_secs)
+    });
+    for (id, _, _) in overdue {
+        warn!(connection_id = id, "Killing TiDB query running for over {}s", limit_secs);
+        // The statement may have finished in the meantime; that is not worth a warning
+        if let Err(err) = conn.execute(format!("KILL QUERY {}", id).as_str()).await {
+            debug!(connection_id = id, "Failed to kill TiDB query: {}", err);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use super::*;
+    use crate::{build_pool_from_config, test_support::integration_config};
+
+    /// Test that sweeps run every half limit, and at least once per second.
+    #[test]
+    fn test_sweep_period() {
+        assert_eq!(sweep_period(10), Duration::from_secs(5));
+        assert_eq!(sweep_period(3), Duration::from_millis(1500));
+        assert_eq!(sweep_period(1), Duration::from_secs(1));
+    }
+
+    /// Test that only the pool's own connections are looked up.
+    #[test]
+    fn test_processlist_query() {
+        let query = processlist_query("information_schema.cluster_processlist", &[7, 42]);
+        assert!(query.ends_with("FROM information_schema.cluster_processlist WHERE ID IN (7, 42)"));
+    }
+
+    /// Test that the watchdog stops once the pool is dropped without being closed.
+    #[tokio::test]
+    async fn test_watchdog_stops_with_pool() {
+        let watchdog = QueryWatchdog {
+            ids: ConnectionIds::default(),
+            limit_secs: 1,
+        };
+        let pool_ids = watchdog.connection_ids().clone();
+        let task = watchdog.spawn(MySqlConnectOptions::new());
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(!task.is_finished());
+
+        drop(pool_ids);
+        tokio::time::timeout(Duration::from_secs(3), task)
+            .await
+            .expect("Watchdog did not stop")
+            .unwrap();
+    }
+
+    /// Test that a statement running past the limit is killed by the watchdog, even when it
+    /// holds every connection of the pool.
+    #[tokio::test]
+    #[ignore = "requires a running TiDB instance"]
+    async fn test_query_watchdog_kills_long_query() {
+        let mut config = integration_config();
+        config.pool_options.query_watchdog_secs = Some(1);
+        config.pool_options.max_connections = 1;
+        config.pool_options.min_connections = 1;
+        let pool = build_pool_from_config(config)
+            .await
+            .expect("Failed to build pool");
+
+        let started = Instant::now();
+        // Depending on the server, a killed SLEEP fails or returns 1 early
+        let _ = sqlx::query("SELECT SLEEP(20)").execute(&pool).await;
+
+        assert!(started.elapsed() < Duration::from_secs(10), "{:?}", started.elapsed());
+    }
+}